@@ -0,0 +1,88 @@
+//! Problems found while parsing a stylesheet, with their position in the
+//! source. None of them is fatal: the offending declaration or rule is
+//! ignored.
+
+use std::fmt;
+
+use crate::tokenizer::offset_in;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+  Warning,
+  Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+  pub severity: Severity,
+  /// See [Stylesheet](crate::Stylesheet).
+  pub origin: String,
+  /// 1-based.
+  pub line: usize,
+  /// 1-based, in characters.
+  pub column: usize,
+  pub property: Option<String>,
+  pub message: String,
+  pub snippet: String,
+}
+
+impl fmt::Display for Severity {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Severity::Warning => write!(f, "warning"),
+      Severity::Error => write!(f, "error"),
+    }
+  }
+}
+
+impl fmt::Display for Diagnostic {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}:{}:{}: {}: {}", self.origin, self.line, self.column, self.severity, self.message)?;
+    if !self.snippet.is_empty() {
+      write!(f, " `{}`", self.snippet)?;
+    }
+    Ok(())
+  }
+}
+
+/// Collects diagnostics for slices of `source`.
+pub(crate) struct Diagnostics<'a> {
+  origin: &'a str,
+  source: &'a str,
+  pub(crate) list: Vec<Diagnostic>,
+}
+
+impl<'a> Diagnostics<'a> {
+  pub(crate) fn new(origin: &'a str, source: &'a str) -> Self {
+    Diagnostics {
+      origin,
+      source,
+      list: Vec::new(),
+    }
+  }
+
+  /// 1-based line of `snippet`, which must borrow the source.
+  pub(crate) fn line(&self, snippet: &str) -> usize {
+    self.source[..offset_in(self.source, snippet)].matches('\n').count() + 1
+  }
+
+  pub(crate) fn origin(&self) -> &'a str {
+    self.origin
+  }
+
+  /// `snippet` must borrow the source.
+  pub(crate) fn report(&mut self, severity: Severity, snippet: &str, property: Option<&str>, message: String) {
+    let before = &self.source[..offset_in(self.source, snippet)];
+    let line = self.line(snippet);
+    let column = before.rsplit('\n').next().map_or(0, |l| l.chars().count()) + 1;
+    self.list.push(Diagnostic {
+      severity,
+      origin: self.origin.into(),
+      line,
+      column,
+      property: property.map(Into::into),
+      message,
+      snippet: snippet.into(),
+    });
+  }
+}
@@ -0,0 +1,36 @@
+//! `@font-face` blocks, to load font files. The family can then be used in
+//! `font-family`.
+//!
+//! ```css
+//! @font-face {
+//!   font-family: "Fira Sans";
+//!   src: url("fonts/FiraSans-Regular.otf"), url("/usr/share/fonts/FiraSans-Regular.ttf");
+//! }
+//!
+//! label {
+//!   font-family: "Fira Sans";
+//! }
+//! ```
+//!
+//! Relative paths are resolved against the directory of the stylesheet. The
+//! sources are alternatives: the first one that can be read is used. A
+//! directory source loads all the font files it contains, by name.
+//!
+//! Faces for `regular`, `bold` and `mono` take precedence over the bundled
+//! fonts. Faces for `fallback` are used by all the families, in source order,
+//! for the glyphs their own fonts lack:
+//!
+//! ```css
+//! @font-face {
+//!   font-family: fallback;
+//!   src: url("/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc"), url("C:/Windows/Fonts/msyh.ttc");
+//! }
+//! ```
+
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FontFace {
+  pub family: String,
+  pub sources: Vec<PathBuf>,
+}
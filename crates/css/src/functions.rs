@@ -0,0 +1,199 @@
+//! Color functions, evaluated after variable substitution.
+//!
+//! - `lighten(color, amount)` and `darken(color, amount)` change the lightness,
+//!   in HSL, by `amount` (`10%` or `0.1`).
+//! - `mix(a, b, weight)` blends two colors. `weight` is the proportion of `a`,
+//!   50% by default.
+//! - `alpha(color, alpha)` replaces the opacity.
+//!
+//! Arguments can be functions too. Each call is replaced by an `rgba()` color.
+
+use std::fmt::Write;
+
+use anyhow::{anyhow, bail, Result};
+use css_color_parser::Color;
+
+use crate::tokenizer;
+use crate::variables::find_closing_parenthesis;
+
+const FUNCTIONS: [&str; 4] = ["lighten", "darken", "mix", "alpha"];
+
+pub(crate) fn evaluate(value: &str) -> Result<String> {
+  let mut evaluated = String::with_capacity(value.len());
+  let mut rest = value;
+  while let Some((start, name)) = find_function(rest) {
+    evaluated.push_str(&rest[..start]);
+    let args_start = start + name.len() + 1;
+    let args_len = find_closing_parenthesis(&rest[args_start..]).ok_or_else(|| anyhow!("Missing `)` in `{}`", value))?;
+    let args: Vec<String> = tokenizer::split(&rest[args_start..args_start + args_len], ',')
+      .into_iter()
+      .map(|arg| evaluate(arg.trim()))
+      .try_collect()?;
+    let color = call(name, &args)?;
+    write!(evaluated, "rgba({}, {}, {}, {})", color.r, color.g, color.b, color.a)?;
+    rest = &rest[args_start + args_len + 1..];
+  }
+  evaluated.push_str(rest);
+  Ok(evaluated)
+}
+
+// Position and name of the next function call.
+fn find_function(value: &str) -> Option<(usize, &'static str)> {
+  let mut start = 0;
+  for (index, c) in value.char_indices() {
+    if c == '(' {
+      let name = &value[start..index];
+      if let Some(name) = FUNCTIONS.iter().find(|function| **function == name) {
+        return Some((start, name));
+      }
+    }
+    if !(c.is_alphanumeric() || c == '-' || c == '_') {
+      start = index + c.len_utf8();
+    }
+  }
+  None
+}
+
+fn call(name: &str, args: &[String]) -> Result<Color> {
+  match (name, args) {
+    ("lighten", [color, amount]) => Ok(lighten(parse_color(color)?, parse_amount(amount)?)),
+    ("darken", [color, amount]) => Ok(lighten(parse_color(color)?, -parse_amount(amount)?)),
+    ("mix", [a, b]) => Ok(mix(parse_color(a)?, parse_color(b)?, 0.5)),
+    ("mix", [a, b, weight]) => Ok(mix(parse_color(a)?, parse_color(b)?, parse_amount(weight)?)),
+    ("alpha", [color, alpha]) => {
+      Ok(Color {
+        a: parse_amount(alpha)?.clamp(0.0, 1.0),
+        ..parse_color(color)?
+      })
+    },
+    _ => bail!("Invalid arguments for {}()", name),
+  }
+}
+
+fn parse_color(text: &str) -> Result<Color> {
+  text.parse().ok().ok_or_else(|| anyhow!("Invalid color: {}", text))
+}
+
+// `10%` or `0.1`.
+fn parse_amount(text: &str) -> Result<f32> {
+  let amount = match text.strip_suffix('%') {
+    Some(percent) => percent.trim().parse::<f32>().map(|percent| percent / 100.0),
+    None => text.parse(),
+  };
+  amount.ok().ok_or_else(|| anyhow!("Invalid amount: {}", text))
+}
+
+fn to_channel(value: f32) -> u8 {
+  (value * 255.0).round().clamp(0.0, 255.0) as u8
+}
+
+fn lighten(color: Color, amount: f32) -> Color {
+  let (h, s, l) = to_hsl(color);
+  let (r, g, b) = from_hsl(h, s, (l + amount).clamp(0.0, 1.0));
+  Color {
+    r: to_channel(r),
+    g: to_channel(g),
+    b: to_channel(b),
+    a: color.a,
+  }
+}
+
+fn mix(a: Color, b: Color, weight: f32) -> Color {
+  let weight = weight.clamp(0.0, 1.0);
+  let blend = |a: u8, b: u8| to_channel((a as f32 * weight + b as f32 * (1.0 - weight)) / 255.0);
+  Color {
+    r: blend(a.r, b.r),
+    g: blend(a.g, b.g),
+    b: blend(a.b, b.b),
+    a: a.a * weight + b.a * (1.0 - weight),
+  }
+}
+
+// Hue in [0, 6), saturation and lightness in [0, 1].
+fn to_hsl(color: Color) -> (f32, f32, f32) {
+  let (r, g, b) = (color.r as f32 / 255.0, color.g as f32 / 255.0, color.b as f32 / 255.0);
+  let max = r.max(g).max(b);
+  let min = r.min(g).min(b);
+  let l = (max + min) / 2.0;
+  let delta = max - min;
+  if delta == 0.0 {
+    return (0.0, 0.0, l);
+  }
+  let s = delta / (1.0 - (2.0 * l - 1.0).abs());
+  let h = if max == r {
+    ((g - b) / delta).rem_euclid(6.0)
+  } else if max == g {
+    (b - r) / delta + 2.0
+  } else {
+    (r - g) / delta + 4.0
+  };
+  (h, s, l)
+}
+
+fn from_hsl(h: f32, s: f32, l: f32) -> (f32, f32, f32) {
+  let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+  let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+  let (r, g, b) = match h as u8 {
+    0 => (chroma, x, 0.0),
+    1 => (x, chroma, 0.0),
+    2 => (0.0, chroma, x),
+    3 => (0.0, x, chroma),
+    4 => (x, 0.0, chroma),
+    _ => (chroma, 0.0, x),
+  };
+  let m = l - chroma / 2.0;
+  (r + m, g + m, b + m)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn eval(value: &str) -> String {
+    evaluate(value).unwrap()
+  }
+
+  #[test]
+  fn lighten_darken() {
+    assert_eq!(eval("lighten(#ff0000, 25%)"), "rgba(255, 128, 128, 1)");
+    assert_eq!(eval("lighten(#ff0000, 0.25)"), "rgba(255, 128, 128, 1)");
+    assert_eq!(eval("darken(#00ff00, 25%)"), "rgba(0, 128, 0, 1)");
+    assert_eq!(eval("darken(#0000ff, 100%)"), "rgba(0, 0, 0, 1)");
+    assert_eq!(eval("lighten(#0000ff, 100%)"), "rgba(255, 255, 255, 1)");
+    assert_eq!(eval("lighten(rgba(255, 0, 0, 0.5), 25%)"), "rgba(255, 128, 128, 0.5)");
+  }
+
+  #[test]
+  fn hsl_round_trip() {
+    for color in ["#000", "#fff", "#808080", "#f06", "#268bd2", "#2aa198", "#b58900", "#6c71c4", "#fdf6e3"] {
+      let color = parse_color(color).unwrap();
+      let same = lighten(color, 0.0);
+      assert_eq!((same.r, same.g, same.b), (color.r, color.g, color.b));
+    }
+  }
+
+  #[test]
+  fn mix_alpha() {
+    assert_eq!(eval("mix(#ff0000, #0000ff)"), "rgba(128, 0, 128, 1)");
+    assert_eq!(eval("mix(#ff0000, #0000ff, 25%)"), "rgba(64, 0, 191, 1)");
+    assert_eq!(eval("mix(#ff0000, #0000ff, 1)"), "rgba(255, 0, 0, 1)");
+    assert_eq!(eval("mix(#ff0000, rgba(0, 0, 255, 0), 0.5)"), "rgba(128, 0, 128, 0.5)");
+    assert_eq!(eval("alpha(#ff0000, 50%)"), "rgba(255, 0, 0, 0.5)");
+    assert_eq!(eval("alpha(#ff0000, 2)"), "rgba(255, 0, 0, 1)");
+  }
+
+  #[test]
+  fn nested() {
+    assert_eq!(eval("1px solid mix(lighten(#000, 100%), #000)"), "1px solid rgba(128, 128, 128, 1)");
+    assert_eq!(eval("alpha(darken(#fff, 100%), 0.25) 2px"), "rgba(0, 0, 0, 0.25) 2px");
+    assert_eq!(eval("premix(#fff, #000)"), "premix(#fff, #000)");
+  }
+
+  #[test]
+  fn errors() {
+    assert_eq!(evaluate("lighten(#fff)").unwrap_err().to_string(), "Invalid arguments for lighten()");
+    assert_eq!(evaluate("lighten(nope, 10%)").unwrap_err().to_string(), "Invalid color: nope");
+    assert_eq!(evaluate("alpha(#fff, half)").unwrap_err().to_string(), "Invalid amount: half");
+    assert!(evaluate("mix(#fff, #000").is_err());
+  }
+}
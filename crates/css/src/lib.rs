@@ -3,7 +3,7 @@
 //! See [ComputedProperties](crate::properties::ComputedProperties)
 //! Variables are defined and used like this.
 //!
-//! ```
+//! ```css
 //! variables {
 //!   pinky: #F06;
 //! }
@@ -12,24 +12,90 @@
 //! }
 //! variables.dark.macos {
 //!   dark-margin: 10;
+//!   dark-padding: var(os-padding) var(dark-margin);
 //! }
 //!
 //! label {
 //!   color: var(pinky);
-//!   padding: var(os-padding);
-//!   margin: var(dark-margin);
+//!   padding: var(os-padding) var(dark-margin, 4);
+//!   margin: var(dark-padding);
 //! }
 //! ```
 //!
+//! `var()` can appear anywhere in a value, and takes an optional fallback used
+//! when the variable is not defined. Variables can reference other variables.
+//!
+//! Lengths are in pixels by default, and also accept `px`, `em` (font-size of
+//! the element), `rem` (font-size of the `native` element) and `%` (of the
+//! available width or height) units.
+//!
+//! Colors can be derived with `lighten(color, 10%)`, `darken(color, 10%)`,
+//! `mix(a, b, 50%)` and `alpha(color, 0.3)`.
+//!
 //! `system_classes` are applied to resolve the variables only.
+//!
+//! Selectors support the ` `, `>` and `+` combinators, and the `:hover`,
+//! `:active`, `:focus`, `:first-child`, `:last-child`, `:nth-child(an+b)` and
+//! `:nth-last-child(an+b)` pseudo-classes. `+` only sees the siblings the UI
+//! attaches to an element: envelope rows know the previous row, but not the one
+//! before, so `a + b + c` never matches them.
+//!
+//! Rules are applied by specificity, then source order, like in CSS.
+//! `!important` declarations win over the other ones. [Rules::explain] lists
+//! the rules matching an element, with their position in the stylesheets.
+//!
+//! `color`, `font-size`, `font-family`, `italics`, `white-space` and
+//! `line-height` are inherited from the parent element. Any property can take
+//! the value of its parent with `inherit`. `em` and `%` font-sizes are relative
+//! to the parent font-size.
+//!
+//! `display`, `direction`, `gap`, `justify-content` and `flex-grow` describe
+//! how containers lay out their children, like a simplified flexbox.
+//!
+//! `@font-face` blocks declare fonts loaded from files. See [FontFace] and
+//! [Rules::font_faces].
+//!
+//! `@media` blocks apply their rules only when the [Environment] matches. See
+//! [Rules::set_environment]. `variables` blocks can't be conditional.
+//!
+//! Several stylesheets can be parsed at once with [parse_stylesheets]. They
+//! behave as if they were concatenated: they share their variables, and later
+//! ones are applied on top of the previous ones.
+//!
+//! [lint] finds unused variables and selectors that can't match.
+//!
+//! [serialize] writes the rules back as a normalized stylesheet.
+//!
+//! Unknown properties, invalid values, undefined variables and unsupported
+//! selectors don't fail the parsing. They are ignored and reported as
+//! [Diagnostic]s.
 
 #![feature(iterator_try_collect)]
 
+mod diagnostics;
+mod font_face;
+mod functions;
+mod lint;
+mod media;
 mod parser;
 mod properties;
 mod rules;
+mod selector;
+mod serializer;
+#[cfg(test)]
+mod testing;
+mod tokenizer;
+mod variables;
 
-pub use parser::parse_css;
-pub use properties::{Align, Color, ComputedProperties, FontFamily, OptionalProperties, Radius, Sides};
-pub use rules::Rules;
-pub use simplecss::{AttributeOperator, Element, PseudoClass};
+pub use diagnostics::{Diagnostic, Severity};
+pub use font_face::FontFace;
+pub use lint::lint;
+pub use media::{ColorScheme, Environment};
+pub use parser::{parse_css, parse_css_str, parse_stylesheets, Stylesheet};
+pub use properties::{
+  Align, BoxShadow, Color, ComputedProperties, Direction, Display, Easing, FontFamily, Justify, Length, OptionalProperties, Radius, Sides, Size, TextOverflow,
+  Transition, Transitions, WhiteSpace,
+};
+pub use rules::{Rule, Rules};
+pub use selector::{AttributeOperator, Element, PseudoClass};
+pub use serializer::{serialize, Values};
@@ -0,0 +1,144 @@
+//! Checks that go beyond parsing, for theme authors:
+//!
+//! - variables that are never used,
+//! - `variables` blocks in `@media` blocks, which are ignored,
+//! - selectors that can't match any element, given the local names the UI uses.
+
+use std::collections::HashSet;
+
+use crate::diagnostics::{Diagnostic, Diagnostics, Severity};
+use crate::parser::{is_variables_selector, Stylesheet};
+use crate::rules::Rules;
+use crate::selector::Selector;
+use crate::{tokenizer, variables};
+
+struct Linter<'a> {
+  local_names: &'a [&'a str],
+  // Variable definitions, with the index of their stylesheet.
+  definitions: Vec<(usize, &'a str)>,
+  references: HashSet<&'a str>,
+}
+
+impl<'a> Linter<'a> {
+  fn blocks(&mut self, index: usize, source: &'a str, in_media: bool, diagnostics: &mut Diagnostics<'_>) {
+    for block in tokenizer::blocks(source) {
+      let body = match block.body {
+        Some(body) => body,
+        None => continue,
+      };
+      if block.prelude.starts_with("@media") {
+        self.blocks(index, body, true, diagnostics);
+        continue;
+      }
+      if block.prelude.starts_with('@') {
+        continue;
+      }
+      let declarations: Vec<_> = tokenizer::declarations(body).into_iter().filter_map(Result::ok).collect();
+      for declaration in &declarations {
+        self.references.extend(variables::references(declaration.value));
+      }
+      for text in tokenizer::split(block.prelude, ',').into_iter().map(str::trim) {
+        let selector = match Selector::parse(text) {
+          Some(selector) => selector,
+          None => continue,
+        };
+        if is_variables_selector(&selector) {
+          if in_media {
+            diagnostics.report(Severity::Warning, text, None, "`variables` blocks are ignored in @media blocks".into());
+          } else {
+            self.definitions.extend(declarations.iter().map(|declaration| (index, declaration.name)));
+          }
+          continue;
+        }
+        let unknown = selector.local_names().find(|name| !self.local_names.contains(name));
+        if let Some(name) = unknown {
+          diagnostics.report(Severity::Warning, text, None, format!("Never matches: no element is named `{}`", name));
+        }
+      }
+    }
+  }
+}
+
+/// `local_names` are the names of all the elements the UI can build.
+pub fn lint(rules: &Rules, local_names: &[&str]) -> Vec<Diagnostic> {
+  let stylesheets: &[Stylesheet] = rules.stylesheets();
+  let mut diagnostics: Vec<Diagnostics<'_>> = stylesheets
+    .iter()
+    .map(|stylesheet| Diagnostics::new(&stylesheet.origin, &stylesheet.source))
+    .collect();
+  let mut linter = Linter {
+    local_names,
+    definitions: Vec::new(),
+    references: HashSet::new(),
+  };
+  for (index, stylesheet) in stylesheets.iter().enumerate() {
+    linter.blocks(index, &stylesheet.source, false, &mut diagnostics[index]);
+  }
+
+  for (index, name) in linter.definitions {
+    if !linter.references.contains(name) {
+      diagnostics[index].report(Severity::Warning, name, None, "Unused variable".into());
+    }
+  }
+
+  let mut list = Vec::new();
+  for diagnostics in diagnostics {
+    let mut diagnostics = diagnostics.list;
+    diagnostics.sort_by_key(|diagnostic| (diagnostic.line, diagnostic.column));
+    list.append(&mut diagnostics);
+  }
+  list
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::parser::parse_stylesheets;
+
+  const LOCAL_NAMES: [&str; 3] = ["native", "label", "hbox"];
+
+  // Messages, with their stylesheet and line.
+  fn lint(sources: &[&str]) -> Vec<String> {
+    let stylesheets = sources
+      .iter()
+      .enumerate()
+      .map(|(index, source)| Stylesheet::new(&format!("{}", index), source))
+      .collect();
+    let (rules, _) = parse_stylesheets(stylesheets, &[]);
+    super::lint(&rules, &LOCAL_NAMES)
+      .iter()
+      .map(|d| format!("{}:{}: {}", d.origin, d.line, d.message))
+      .collect()
+  }
+
+  #[test]
+  fn unused_variables() {
+    let source = "variables {\n  used: red;\n  unused: blue;\n  fallback: 1;\n}\nlabel { color: var(used); padding: var(missing, var(fallback)); }";
+    assert_eq!(lint(&[source]), ["0:3: Unused variable"]);
+    // Used in another stylesheet, or by another variable.
+    assert!(lint(&["variables { a: red; }", "label { color: var(a); }"]).is_empty());
+    assert!(lint(&["variables { a: red; b: var(a); }\nlabel { color: var(b); }"]).is_empty());
+    assert_eq!(
+      lint(&["variables { a: red; }", "variables { a: blue; }"]),
+      ["0:1: Unused variable", "1:1: Unused variable"]
+    );
+  }
+
+  #[test]
+  fn variables_in_media() {
+    let source = "variables { a: red; }\n@media (os: linux) {\n  variables { a: blue; }\n  label { color: var(a); }\n}";
+    assert_eq!(lint(&[source]), ["0:3: `variables` blocks are ignored in @media blocks"]);
+  }
+
+  #[test]
+  fn unknown_local_names() {
+    let source = "label, button { color: red; }\nhbox > .a, hbox:hover #b, *, .c { color: red; }\n@media (os: linux) {\n  vbox label { color: red; }\n}";
+    assert_eq!(
+      lint(&[source]),
+      [
+        "0:1: Never matches: no element is named `button`",
+        "0:4: Never matches: no element is named `vbox`"
+      ]
+    );
+  }
+}
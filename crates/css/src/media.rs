@@ -0,0 +1,151 @@
+//! `@media` blocks. Their rules only apply when the environment matches.
+//!
+//! ```css
+//! @media (max-width: 700px), (os: linux) and (prefers-color-scheme: dark) {
+//!   #envelopes-frame { padding: 0; }
+//! }
+//! ```
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ColorScheme {
+  #[default]
+  Light,
+  Dark,
+}
+
+/// What `@media` conditions are evaluated against.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Environment {
+  /// Window size, in points.
+  pub width: f32,
+  pub height: f32,
+  pub color_scheme: ColorScheme,
+  /// As in `std::env::consts::OS`.
+  pub os: String,
+}
+
+impl Default for Environment {
+  fn default() -> Self {
+    Environment {
+      width: 0.0,
+      height: 0.0,
+      color_scheme: ColorScheme::default(),
+      os: std::env::consts::OS.into(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Feature {
+  ColorScheme(ColorScheme),
+  Os(String),
+  MinWidth(f32),
+  MaxWidth(f32),
+  MinHeight(f32),
+  MaxHeight(f32),
+}
+
+impl Feature {
+  fn matches(&self, env: &Environment) -> bool {
+    match self {
+      Feature::ColorScheme(scheme) => env.color_scheme == *scheme,
+      Feature::Os(os) => env.os == *os,
+      Feature::MinWidth(width) => env.width >= *width,
+      Feature::MaxWidth(width) => env.width <= *width,
+      Feature::MinHeight(height) => env.height >= *height,
+      Feature::MaxHeight(height) => env.height <= *height,
+    }
+  }
+}
+
+/// Comma separated conditions, each made of features joined with `and`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct MediaQuery(pub(crate) Vec<Vec<Feature>>);
+
+impl MediaQuery {
+  pub(crate) fn matches(&self, env: &Environment) -> bool {
+    self.0.iter().any(|condition| condition.iter().all(|feature| feature.matches(env)))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse(text: &str) -> MediaQuery {
+    text.parse().unwrap_or_else(|e| panic!("{}: {}", text, e))
+  }
+
+  fn env(width: f32, color_scheme: ColorScheme, os: &str) -> Environment {
+    Environment {
+      width,
+      height: 500.0,
+      color_scheme,
+      os: os.into(),
+    }
+  }
+
+  #[test]
+  fn parse_queries() {
+    assert_eq!(parse("(max-width: 700px)"), MediaQuery(vec![vec![Feature::MaxWidth(700.0)]]));
+    assert_eq!(parse("(min-height:300)"), MediaQuery(vec![vec![Feature::MinHeight(300.0)]]));
+    assert_eq!(
+      parse(" (os: linux)  and\t(prefers-color-scheme: dark) "),
+      MediaQuery(vec![vec![Feature::Os("linux".into()), Feature::ColorScheme(ColorScheme::Dark)]])
+    );
+    assert_eq!(
+      parse("(max-width: 700px), (os: macos) and (min-width: 100)"),
+      MediaQuery(vec![
+        vec![Feature::MaxWidth(700.0)],
+        vec![Feature::Os("macos".into()), Feature::MinWidth(100.0)]
+      ])
+    );
+  }
+
+  #[test]
+  fn parse_invalid() {
+    for text in [
+      "",
+      "max-width: 700px",
+      "(max-width: 700px",
+      "(max-width 700px)",
+      "(max-width: 10em)",
+      "(max-width: wide)",
+      "(prefers-color-scheme: blue)",
+      "(orientation: portrait)",
+      "(os: linux) (min-width: 100)",
+      "(os: linux) or (min-width: 100)",
+      "(os: linux)and (min-width: 100)",
+      "(os: linux) and(min-width: 100)",
+      "(os: linux) andand (min-width: 100)",
+      "(os: linux) and",
+      "(os: linux),",
+    ] {
+      assert!(text.parse::<MediaQuery>().is_err(), "{}", text);
+    }
+  }
+
+  #[test]
+  fn matches() {
+    let narrow = parse("(max-width: 700px)");
+    assert!(narrow.matches(&env(700.0, ColorScheme::Light, "linux")));
+    assert!(!narrow.matches(&env(701.0, ColorScheme::Light, "linux")));
+
+    let dark_linux = parse("(os: linux) and (prefers-color-scheme: dark)");
+    assert!(dark_linux.matches(&env(800.0, ColorScheme::Dark, "linux")));
+    assert!(!dark_linux.matches(&env(800.0, ColorScheme::Light, "linux")));
+    assert!(!dark_linux.matches(&env(800.0, ColorScheme::Dark, "macos")));
+
+    let either = parse("(max-width: 700px), (os: linux) and (prefers-color-scheme: dark)");
+    assert!(either.matches(&env(600.0, ColorScheme::Light, "macos")));
+    assert!(either.matches(&env(800.0, ColorScheme::Dark, "linux")));
+    assert!(!either.matches(&env(800.0, ColorScheme::Light, "linux")));
+
+    let height = parse("(min-height: 400) and (max-height: 600)");
+    assert!(height.matches(&env(0.0, ColorScheme::Light, "linux")));
+    assert!(!height.matches(&Environment {
+      height: 700.0,
+      ..env(0.0, ColorScheme::Light, "linux")
+    }));
+  }
+}
@@ -1,31 +1,65 @@
-use std::collections::{HashMap, HashSet};
-use std::path::Path;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
-use anyhow::{bail, Error, Result};
-use simplecss::{AttributeOperator, Element, PseudoClass, StyleSheet};
+use anyhow::{anyhow, bail, Error, Result};
+use simplecss::Declaration;
 
-use crate::properties::{parse_declarations, Align, FontFamily, Radius, Sides};
-use crate::rules::{Rule, Rules, RulesBuilder};
+use crate::diagnostics::{Diagnostic, Diagnostics, Severity};
+use crate::font_face::FontFace;
+use crate::media::{ColorScheme, Environment, Feature, MediaQuery};
+use crate::properties::{
+  parse_declarations, Align, BoxShadow, Color, Direction, Display, Easing, FontFamily, Justify, Length, Radius, Sides, Size, TextOverflow, Transition,
+  Transitions, WhiteSpace,
+};
+use crate::rules::{next_generation, Rule, Rules, RulesBuilder};
+use crate::selector::{AttributeOperator, Element, PseudoClass, Selector};
+use crate::tokenizer;
+use crate::variables::Variables;
+
+// Text between single or double quotes.
+fn unquote(text: &str) -> Option<&str> {
+  ['"', '\''].into_iter().find_map(|quote| text.strip_prefix(quote)?.strip_suffix(quote))
+}
 
 impl FromStr for FontFamily {
   type Err = Error;
 
   fn from_str(line: &str) -> Result<Self> {
+    if let Some(name) = unquote(line) {
+      return Ok(FontFamily::Named(name.into()));
+    }
     match line.to_lowercase().as_str() {
       "bold" => Ok(FontFamily::Bold),
       "regular" => Ok(FontFamily::Regular),
       "mono" => Ok(FontFamily::Mono),
+      _ if line.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == ' ') => Ok(FontFamily::Named(line.into())),
       _ => bail!("Unknown font-family"),
     }
   }
 }
 
+impl FromStr for Length {
+  type Err = Error;
+
+  fn from_str(word: &str) -> Result<Self> {
+    let unit_start = word.find(|c: char| c.is_ascii_alphabetic() || c == '%').unwrap_or(word.len());
+    let value: f32 = word[..unit_start].parse()?;
+    match &word[unit_start..] {
+      "" | "px" => Ok(Length::Px(value)),
+      "em" => Ok(Length::Em(value)),
+      "rem" => Ok(Length::Rem(value)),
+      "%" => Ok(Length::Percent(value)),
+      unit => bail!("Unknown unit: {}", unit),
+    }
+  }
+}
+
 impl FromStr for Sides {
   type Err = Error;
 
   fn from_str(line: &str) -> Result<Self> {
-    let words: Vec<f32> = line.split_whitespace().map(|word| word.parse()).try_collect()?;
+    let words: Vec<Length> = line.split_whitespace().map(|word| word.parse()).try_collect()?;
     match words.len() {
       1 => {
         Ok(Sides {
@@ -60,7 +94,7 @@ impl FromStr for Radius {
   type Err = Error;
 
   fn from_str(line: &str) -> Result<Self> {
-    let words: Vec<f32> = line.split_whitespace().map(|word| word.parse()).try_collect()?;
+    let words: Vec<Length> = line.split_whitespace().map(|word| word.parse()).try_collect()?;
     match words.len() {
       1 => {
         Ok(Radius {
@@ -83,6 +117,19 @@ impl FromStr for Radius {
   }
 }
 
+impl FromStr for Size {
+  type Err = Error;
+
+  fn from_str(line: &str) -> Result<Self> {
+    let words: Vec<Length> = line.split_whitespace().map(|word| word.parse()).try_collect()?;
+    match words[..] {
+      [size] => Ok(Size { x: size, y: size }),
+      [x, y] => Ok(Size { x, y }),
+      _ => bail!("Parsing size failed. Expected 1 or 2 values"),
+    }
+  }
+}
+
 impl FromStr for Align {
   type Err = Error;
 
@@ -96,6 +143,272 @@ impl FromStr for Align {
   }
 }
 
+impl FromStr for Direction {
+  type Err = Error;
+
+  fn from_str(word: &str) -> Result<Self> {
+    match word {
+      "row" => Ok(Direction::Row),
+      "column" => Ok(Direction::Column),
+      _ => bail!("Invalid direction value"),
+    }
+  }
+}
+
+impl FromStr for Justify {
+  type Err = Error;
+
+  fn from_str(word: &str) -> Result<Self> {
+    match word {
+      "start" | "flex-start" => Ok(Justify::Start),
+      "center" => Ok(Justify::Center),
+      "end" | "flex-end" => Ok(Justify::End),
+      "space-between" => Ok(Justify::SpaceBetween),
+      _ => bail!("Invalid justify-content value"),
+    }
+  }
+}
+
+impl FromStr for Display {
+  type Err = Error;
+
+  fn from_str(word: &str) -> Result<Self> {
+    match word {
+      "block" | "flex" => Ok(Display::Block),
+      "none" => Ok(Display::None),
+      _ => bail!("Invalid display value"),
+    }
+  }
+}
+
+impl FromStr for TextOverflow {
+  type Err = Error;
+
+  fn from_str(word: &str) -> Result<Self> {
+    match word {
+      "clip" => Ok(TextOverflow::Clip),
+      "ellipsis" => Ok(TextOverflow::Ellipsis),
+      _ => bail!("Invalid text-overflow value"),
+    }
+  }
+}
+
+impl FromStr for WhiteSpace {
+  type Err = Error;
+
+  fn from_str(word: &str) -> Result<Self> {
+    match word {
+      "nowrap" => Ok(WhiteSpace::NoWrap),
+      "wrap" | "normal" => Ok(WhiteSpace::Wrap),
+      _ => bail!("Invalid white-space value"),
+    }
+  }
+}
+
+impl FromStr for BoxShadow {
+  type Err = Error;
+
+  fn from_str(line: &str) -> Result<Self> {
+    let mut rest = line.trim();
+    if rest == "none" {
+      return Ok(BoxShadow::default());
+    }
+    // Lengths come first or last. The color can contain spaces.
+    let mut lengths = Vec::new();
+    while lengths.len() < 3 {
+      let (word, tail) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+      match word.parse::<Length>() {
+        Ok(length) => lengths.push(length),
+        Err(_) => break,
+      }
+      rest = tail.trim_start();
+    }
+    let color_first = lengths.is_empty();
+    while color_first && lengths.len() < 3 {
+      let (head, word) = rest.rsplit_once(char::is_whitespace).unwrap_or(("", rest));
+      match word.parse::<Length>() {
+        Ok(length) => lengths.insert(0, length),
+        Err(_) => break,
+      }
+      rest = head.trim_end();
+    }
+    let color: Color = rest.parse().ok().ok_or_else(|| anyhow!("Invalid shadow color"))?;
+    let (x, y, blur) = match lengths[..] {
+      [x, y] => (x, y, Length::default()),
+      [x, y, blur] => (x, y, blur),
+      _ => bail!("Parsing box-shadow failed. Expected 2 or 3 lengths and a color"),
+    };
+    // egui shadows are centered.
+    if x.resolve(1.0, 1.0, 1.0) != 0.0 || y.resolve(1.0, 1.0, 1.0) != 0.0 {
+      bail!("box-shadow offsets are not supported, use `0 0`");
+    }
+    Ok(BoxShadow { x, y, blur, color })
+  }
+}
+
+impl FromStr for Easing {
+  type Err = Error;
+
+  fn from_str(word: &str) -> Result<Self> {
+    match word {
+      "linear" => Ok(Easing::Linear),
+      "ease" => Ok(Easing::Ease),
+      "ease-in" => Ok(Easing::EaseIn),
+      "ease-out" => Ok(Easing::EaseOut),
+      "ease-in-out" => Ok(Easing::EaseInOut),
+      _ => bail!("Unknown easing: {}", word),
+    }
+  }
+}
+
+// `0.2s` or `200ms`, in seconds.
+fn parse_duration(word: &str) -> Result<f32> {
+  match (word.strip_suffix("ms"), word.strip_suffix('s')) {
+    (Some(ms), _) => Ok(ms.parse::<f32>()? / 1000.0),
+    (None, Some(s)) => Ok(s.parse()?),
+    _ => bail!("Expected a duration in s or ms: {}", word),
+  }
+}
+
+impl FromStr for Transition {
+  type Err = Error;
+
+  fn from_str(line: &str) -> Result<Self> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let (property, duration, easing) = match words[..] {
+      [property, duration] => (property, duration, Easing::default()),
+      [property, duration, easing] => (property, duration, easing.parse()?),
+      _ => bail!("Parsing transition failed. Expected a property, a duration and an optional easing"),
+    };
+    Ok(Transition {
+      property: property.into(),
+      duration: parse_duration(duration)?,
+      easing,
+    })
+  }
+}
+
+impl FromStr for Transitions {
+  type Err = Error;
+
+  fn from_str(line: &str) -> Result<Self> {
+    if line.trim() == "none" {
+      return Ok(Transitions::default());
+    }
+    Ok(Transitions(line.split(',').map(str::parse).try_collect()?))
+  }
+}
+
+impl FromStr for ColorScheme {
+  type Err = Error;
+
+  fn from_str(word: &str) -> Result<Self> {
+    match word {
+      "light" => Ok(ColorScheme::Light),
+      "dark" => Ok(ColorScheme::Dark),
+      _ => bail!("Unknown color scheme: {}", word),
+    }
+  }
+}
+
+// Sizes are in pixels.
+fn parse_size(word: &str) -> Result<f32> {
+  match word.parse()? {
+    Length::Px(size) => Ok(size),
+    _ => bail!("Expected a size in px: {}", word),
+  }
+}
+
+impl FromStr for Feature {
+  type Err = Error;
+
+  fn from_str(text: &str) -> Result<Self> {
+    let (name, value) = text.split_once(':').ok_or_else(|| anyhow!("Expected `name: value`"))?;
+    let value = value.trim();
+    match name.trim() {
+      "prefers-color-scheme" => Ok(Feature::ColorScheme(value.parse()?)),
+      "os" => Ok(Feature::Os(value.into())),
+      "min-width" => Ok(Feature::MinWidth(parse_size(value)?)),
+      "max-width" => Ok(Feature::MaxWidth(parse_size(value)?)),
+      "min-height" => Ok(Feature::MinHeight(parse_size(value)?)),
+      "max-height" => Ok(Feature::MaxHeight(parse_size(value)?)),
+      name => bail!("Unknown media feature: {}", name),
+    }
+  }
+}
+
+// `(feature) and (feature)…`
+fn parse_media_condition(mut text: &str) -> Result<Vec<Feature>> {
+  let mut features = Vec::new();
+  loop {
+    let rest = text.trim_start().strip_prefix('(').ok_or_else(|| anyhow!("Expected `(`"))?;
+    let (feature, rest) = rest.split_once(')').ok_or_else(|| anyhow!("Expected `)`"))?;
+    features.push(feature.parse()?);
+    if rest.trim().is_empty() {
+      return Ok(features);
+    }
+    // `and` is a keyword, surrounded by whitespace.
+    text = match rest.strip_prefix(char::is_whitespace).and_then(|rest| rest.trim_start().strip_prefix("and")) {
+      Some(rest) if rest.starts_with(char::is_whitespace) => rest,
+      _ => bail!("Expected ` and `"),
+    };
+  }
+}
+
+impl FromStr for MediaQuery {
+  type Err = Error;
+
+  fn from_str(text: &str) -> Result<Self> {
+    let conditions = tokenizer::split(text, ',').into_iter().map(parse_media_condition).try_collect()?;
+    Ok(MediaQuery(conditions))
+  }
+}
+
+// `url(path)`, `url("path")` or `"path"`. Anything after the url, like a
+// `format()` hint, is ignored.
+fn parse_url(text: &str) -> Result<&str> {
+  if let Some(path) = unquote(text) {
+    return Ok(path);
+  }
+  let args = text.strip_prefix("url(").ok_or_else(|| anyhow!("Expected url()"))?;
+  let end = args.find(')').ok_or_else(|| anyhow!("Missing `)`"))?;
+  let path = args[..end].trim();
+  Ok(unquote(path).unwrap_or(path))
+}
+
+pub(crate) fn parse_font_face(prelude: &str, body: &str, dir: Option<&Path>, diagnostics: &mut Diagnostics<'_>) -> Option<FontFace> {
+  let mut family = None;
+  let mut sources = Vec::new();
+  for declaration in tokenizer::declarations(body) {
+    let declaration = match declaration {
+      Ok(declaration) => declaration,
+      Err(text) => {
+        diagnostics.report(Severity::Error, text, None, "Invalid declaration".into());
+        continue;
+      },
+    };
+    match declaration.name {
+      "font-family" => family = Some(unquote(declaration.value).unwrap_or(declaration.value).to_string()),
+      "src" => {
+        for source in tokenizer::split(declaration.value, ',').into_iter().map(tokenizer::trim_comments) {
+          match parse_url(source) {
+            Ok(path) => sources.push(dir.map_or_else(|| PathBuf::from(path), |dir| dir.join(path))),
+            Err(e) => diagnostics.report(Severity::Error, source, Some("src"), format!("Invalid source: {}", e)),
+          }
+        }
+      },
+      name => diagnostics.report(Severity::Warning, name, Some(name), "Unknown @font-face descriptor".into()),
+    }
+  }
+  match family {
+    Some(family) if !sources.is_empty() => Some(FontFace { family, sources }),
+    _ => {
+      diagnostics.report(Severity::Error, prelude, None, "@font-face needs a font-family and a src".into());
+      None
+    },
+  }
+}
+
 // We use this element just early on during the parsing process
 // to find variables to later on resolve the property valus.
 struct VariableElement<'a> {
@@ -127,48 +440,286 @@ impl<'a> Element for VariableElement<'a> {
     None
   }
 
-  fn pseudo_class_matches(&self, _class: PseudoClass<'_>) -> bool {
+  fn pseudo_class_matches(&self, _class: PseudoClass) -> bool {
     false
   }
 }
 
-pub fn parse_css(path: &str, system_classes: &[&str]) -> Result<Rules> {
+// Selectors of the `variables` blocks. `*` also matches a `VariableElement`,
+// so the specificity needs to be checked too.
+fn is_variables(selector: &Selector<'_>, var_elt: &VariableElement<'_>) -> bool {
+  selector.matches(var_elt) && selector.specificity()[2] != 0
+}
+
+pub(crate) fn is_variables_selector(selector: &Selector<'_>) -> bool {
+  is_variables(selector, &VariableElement { classes: HashSet::new() })
+}
+
+// Selectors, declarations, the `@media` block they're in, and the selectors
+// text.
+type Block<'a> = (Vec<Selector<'a>>, Vec<Declaration<'a>>, Option<MediaQuery>, &'a str);
+
+fn parse_blocks<'a>(
+  source: &'a str,
+  media: Option<&MediaQuery>,
+  dir: Option<&Path>,
+  font_faces: &mut Vec<FontFace>,
+  diagnostics: &mut Diagnostics<'_>,
+) -> Vec<Block<'a>> {
+  let mut blocks = Vec::new();
+  for block in tokenizer::blocks(source) {
+    if !block.closed {
+      diagnostics.report(Severity::Error, block.prelude, None, "Missing `}`".into());
+    }
+    // `@font-face` blocks must be at the top level.
+    if let (true, Some(body), None) = (block.prelude == "@font-face", block.body, media) {
+      font_faces.extend(parse_font_face(block.prelude, body, dir, diagnostics));
+      continue;
+    }
+    let media_query = block
+      .prelude
+      .strip_prefix("@media")
+      .filter(|query| query.starts_with(|c: char| c.is_whitespace() || c == '('));
+    // `@media` blocks can't be nested.
+    if let (Some(query), Some(body), None) = (media_query, block.body, media) {
+      match query.parse::<MediaQuery>() {
+        Ok(media) => blocks.append(&mut parse_blocks(body, Some(&media), dir, font_faces, diagnostics)),
+        Err(e) => diagnostics.report(Severity::Error, block.prelude, None, format!("Invalid @media: {}", e)),
+      }
+      continue;
+    }
+    let body = match block.body {
+      _ if block.prelude.starts_with('@') => {
+        diagnostics.report(Severity::Warning, block.prelude, None, "Unsupported at-rule".into());
+        continue;
+      },
+      Some(body) => body,
+      None => {
+        diagnostics.report(Severity::Error, block.prelude, None, "Expected `{`".into());
+        continue;
+      },
+    };
+
+    let declarations: Vec<Declaration<'_>> = tokenizer::declarations(body)
+      .into_iter()
+      .filter_map(|dec| {
+        dec
+          .map_err(|text| diagnostics.report(Severity::Error, text, None, "Invalid declaration".into()))
+          .ok()
+      })
+      .collect();
+
+    let selectors = tokenizer::split(block.prelude, ',')
+      .into_iter()
+      .map(str::trim)
+      .filter_map(|text| {
+        let selector = Selector::parse(text);
+        if selector.is_none() {
+          diagnostics.report(Severity::Warning, text, None, "Unsupported selector".into());
+        }
+        selector
+      })
+      .collect();
+
+    blocks.push((selectors, declarations, media.cloned(), block.prelude));
+  }
+  blocks
+}
+
+/// A stylesheet and where it comes from (a path, or a name for built-in
+/// stylesheets). Used in diagnostics.
+#[derive(Clone)]
+pub struct Stylesheet {
+  pub origin: String,
+  pub source: String,
+  /// Relative `@font-face` sources are resolved against this directory, or
+  /// the working directory if `None`.
+  pub dir: Option<PathBuf>,
+}
+
+impl Stylesheet {
+  pub fn new(origin: &str, source: &str) -> Stylesheet {
+    Stylesheet {
+      origin: origin.into(),
+      source: source.into(),
+      dir: None,
+    }
+  }
+
+  pub fn from_path(path: impl AsRef<Path>) -> Result<Stylesheet> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path)?;
+    Ok(Stylesheet {
+      origin: path.display().to_string(),
+      source,
+      dir: path.parent().map(Path::to_path_buf),
+    })
+  }
+}
+
+pub fn parse_css(path: &str, system_classes: &[&str]) -> Result<(Rules, Vec<Diagnostic>)> {
+  Ok(parse_stylesheets(vec![Stylesheet::from_path(path)?], system_classes))
+}
+
+pub fn parse_css_str(source: &str, system_classes: &[&str]) -> (Rules, Vec<Diagnostic>) {
+  parse_stylesheets(vec![Stylesheet::new("<string>", source)], system_classes)
+}
+
+/// Later stylesheets are applied on top of the previous ones, as if they were
+/// concatenated. Variables are shared.
+pub fn parse_stylesheets(stylesheets: Vec<Stylesheet>, system_classes: &[&str]) -> (Rules, Vec<Diagnostic>) {
   let classes = system_classes.iter().copied().collect();
   let var_elt = VariableElement { classes: HashSet::new() };
   let system_var_elt = VariableElement { classes };
 
-  let source = std::fs::read_to_string(Path::new(path))?;
-  let rules = RulesBuilder {
-    source,
-    rules_builder: |source: &String| {
-      let stylesheet = StyleSheet::parse(source);
+  let mut diagnostic_list = Vec::new();
+  let mut font_faces = Vec::new();
+  let mut all_variables = Variables::default();
+  let mut rules = RulesBuilder {
+    stylesheets,
+    root_font_size: 0.0,
+    generation: next_generation(),
+    environment: Environment::default(),
+    font_faces: Vec::new(),
+    variables: Variables::default(),
+    rules_builder: |stylesheets: &Vec<Stylesheet>| {
+      let mut parsed: Vec<(Diagnostics<'_>, Vec<Block<'_>>)> = stylesheets
+        .iter()
+        .map(|stylesheet| {
+          let mut diagnostics = Diagnostics::new(&stylesheet.origin, &stylesheet.source);
+          let blocks = parse_blocks(&stylesheet.source, None, stylesheet.dir.as_deref(), &mut font_faces, &mut diagnostics);
+          (diagnostics, blocks)
+        })
+        .collect();
 
-      let mut variables = HashMap::new();
+      // More specific `variables` blocks override the others. They are ignored
+      // in `@media` blocks.
+      let mut variable_blocks: Vec<(&Selector<'_>, &Vec<Declaration<'_>>)> = parsed
+        .iter()
+        .flat_map(|(_, blocks)| blocks)
+        .filter(|(_, _, media, _)| media.is_none())
+        .flat_map(|(selectors, declarations, _, _)| selectors.iter().map(move |selector| (selector, declarations)))
+        .filter(|(selector, _)| is_variables(selector, &var_elt))
+        .collect();
+      variable_blocks.sort_by_cached_key(|(selector, _)| selector.specificity());
 
-      stylesheet.rules.iter().filter(|r| r.selector.matches(&system_var_elt)).for_each(|rule| {
-        for declaration in &rule.declarations {
-          variables.insert(declaration.name.into(), declaration.value.into());
+      let mut variables = Variables::default();
+      for (_, declarations) in variable_blocks.iter().filter(|(selector, _)| selector.matches(&system_var_elt)) {
+        for declaration in declarations.iter() {
+          variables.insert(declaration.name, &tokenizer::strip_comments(declaration.value));
         }
-      });
-
-      stylesheet
-        .rules
-        .into_iter()
-        .filter(|r| {
-          // Ditch `variable {}` rules.
-          // Need to check the spec too, otherwise `* {}` will be ditched too.
-          let spec = r.selector.specificity();
-          !r.selector.matches(&var_elt) || spec[2] == 0
-        })
-        .map(|r| {
-          Rule {
-            selector: r.selector,
-            properties: parse_declarations(&r.declarations, &variables),
+      }
+
+      let mut rules = Vec::new();
+      for (diagnostics, blocks) in &mut parsed {
+        for (selectors, declarations, media, prelude) in blocks.drain(..) {
+          let selectors: Vec<Selector<'_>> = selectors.into_iter().filter(|selector| !is_variables(selector, &var_elt)).collect();
+          if selectors.is_empty() {
+            continue;
           }
-        })
-        .collect()
+          let (important, normal): (Vec<Declaration<'_>>, Vec<Declaration<'_>>) = declarations.into_iter().partition(|dec| dec.important);
+          let properties = parse_declarations(&normal, &variables, diagnostics);
+          let important = parse_declarations(&important, &variables, diagnostics);
+          let line = diagnostics.line(prelude);
+          for selector in selectors {
+            rules.push(Rule {
+              specificity: selector.specificity(),
+              order: rules.len(),
+              origin: diagnostics.origin(),
+              line,
+              selector,
+              properties: properties.clone(),
+              important: important.clone(),
+              active: media.as_ref().map_or(true, |media| media.matches(&Environment::default())),
+              media: media.clone(),
+            });
+          }
+        }
+      }
+
+      for (diagnostics, _) in parsed {
+        let mut list = diagnostics.list;
+        list.sort_by_key(|diagnostic| (diagnostic.line, diagnostic.column));
+        diagnostic_list.append(&mut list);
+      }
+      all_variables = variables;
+      rules
     },
   }
   .build();
-  Ok(rules)
+
+  rules.set_font_faces(font_faces);
+  rules.set_variables(all_variables);
+  rules.update_root_font_size();
+
+  (rules, diagnostic_list)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn length() {
+    assert_eq!("12px".parse::<Length>().unwrap(), Length::Px(12.0));
+    assert_eq!("12".parse::<Length>().unwrap(), Length::Px(12.0));
+    assert_eq!("-1.5px".parse::<Length>().unwrap(), Length::Px(-1.5));
+    assert_eq!("0.5em".parse::<Length>().unwrap(), Length::Em(0.5));
+    assert_eq!("2rem".parse::<Length>().unwrap(), Length::Rem(2.0));
+    assert_eq!("50%".parse::<Length>().unwrap(), Length::Percent(50.0));
+    assert_eq!("12pt".parse::<Length>().unwrap_err().to_string(), "Unknown unit: pt");
+    assert!("px".parse::<Length>().is_err());
+    assert!("".parse::<Length>().is_err());
+    assert!("1 px".parse::<Length>().is_err());
+    assert!("abc".parse::<Length>().is_err());
+  }
+
+  #[test]
+  fn box_shadow() {
+    let shadow = |text: &str| text.parse::<BoxShadow>().unwrap_or_else(|e| panic!("{}: {}", text, e));
+    for text in [
+      "0 0 32 rgba(0, 0, 0, 0.5)",
+      "rgba(0, 0, 0, 0.5) 0 0 32",
+      "0px 0px 2rem rgba(0,0,0,0.5)",
+      "rgba( 0, 0, 0, 0.5 )   0 0 2rem",
+    ] {
+      let shadow = shadow(text);
+      assert_eq!((shadow.x, shadow.y), (Length::default(), Length::default()), "{}", text);
+      assert_ne!(shadow.blur, Length::default(), "{}", text);
+      assert_eq!((shadow.color.r, shadow.color.g, shadow.color.b, shadow.color.a), (0, 0, 0, 0.5), "{}", text);
+    }
+    assert_eq!(shadow("0 0 red").blur, Length::default());
+    assert_eq!(shadow("red 0 0").blur, Length::default());
+    assert_eq!(shadow("none").color.a, 0.0);
+  }
+
+  #[test]
+  fn box_shadow_invalid() {
+    for text in ["", "red", "0 red", "red 0", "0 0", "0 0 0 0 red", "0 0 nocolor", "red 0 0 blue", "0 red 0"] {
+      assert!(text.parse::<BoxShadow>().is_err(), "{}", text);
+    }
+    for text in ["2 0 8 red", "0 -1px red", "red 1em 0 8"] {
+      assert_eq!(
+        text.parse::<BoxShadow>().unwrap_err().to_string(),
+        "box-shadow offsets are not supported, use `0 0`",
+        "{}",
+        text
+      );
+    }
+  }
+
+  #[test]
+  fn comments_in_values() {
+    let (_, diagnostics) = parse_css_str("variables { a: 1 /* x */; }\nb { padding: var(a) /* inline */ 2; margin:1/* x */2 }", &[]);
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics.iter().map(|d| &d.message).collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn unclosed_block() {
+    let (_, diagnostics) = parse_css_str("a { color: red; }\n\nb {\n  color: red;\n", &[]);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+    assert_eq!(diagnostics[0].line, 3);
+    assert_eq!(diagnostics[0].message, "Missing `}`");
+  }
 }
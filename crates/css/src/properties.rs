@@ -1,32 +1,103 @@
-use std::collections::HashMap;
 use std::str::FromStr;
 
+use anyhow::anyhow;
 pub use css_color_parser::Color;
-use log::warn;
 use simplecss::Declaration;
 
-#[derive(Debug, Default, Clone, Copy)]
+use crate::diagnostics::{Diagnostics, Severity};
+use crate::variables::Variables;
+use crate::{functions, tokenizer};
+
+/// One of the bundled fonts, or a family declared with `@font-face`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub enum FontFamily {
   #[default]
   Regular,
   Bold,
   Mono,
+  Named(String),
+}
+
+/// A length. Bare numbers are pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Length {
+  Px(f32),
+  /// Relative to the font-size of the element.
+  Em(f32),
+  /// Relative to the root font-size.
+  Rem(f32),
+  /// Relative to the available width or height.
+  Percent(f32),
+}
+
+impl Default for Length {
+  fn default() -> Self {
+    Length::Px(0.0)
+  }
+}
+
+impl Length {
+  pub fn resolve(&self, font_size: f32, root_font_size: f32, available: f32) -> f32 {
+    match *self {
+      Length::Px(v) => v,
+      Length::Em(v) => v * font_size,
+      Length::Rem(v) => v * root_font_size,
+      Length::Percent(v) => v * available / 100.0,
+    }
+  }
 }
 
 #[derive(Debug, Default, Clone, Copy)]
 pub struct Sides {
-  pub top: f32,
-  pub left: f32,
-  pub right: f32,
-  pub bottom: f32,
+  pub top: Length,
+  pub left: Length,
+  pub right: Length,
+  pub bottom: Length,
 }
 
 #[derive(Debug, Default, Clone, Copy)]
 pub struct Radius {
-  pub nw: f32,
-  pub ne: f32,
-  pub sw: f32,
-  pub se: f32,
+  pub nw: Length,
+  pub ne: Length,
+  pub sw: Length,
+  pub se: Length,
+}
+
+/// Width and height. A single value is used for both.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Size {
+  pub x: Length,
+  pub y: Length,
+}
+
+impl Size {
+  pub const fn px(x: f32, y: f32) -> Size {
+    Size {
+      x: Length::Px(x),
+      y: Length::Px(y),
+    }
+  }
+}
+
+/// `box-shadow: x y [blur] color`, `color x y [blur]` or `none`. egui shadows
+/// are centered, so the offsets must be 0.
+#[derive(Debug, Clone)]
+pub struct BoxShadow {
+  pub x: Length,
+  pub y: Length,
+  pub blur: Length,
+  pub color: Color,
+}
+
+impl Default for BoxShadow {
+  fn default() -> Self {
+    BoxShadow {
+      x: Length::default(),
+      y: Length::default(),
+      blur: Length::default(),
+      color: Color { r: 0, g: 0, b: 0, a: 0.0 },
+    }
+  }
 }
 
 #[derive(Debug, Default, Clone, Copy)]
@@ -37,75 +108,253 @@ pub enum Align {
   Max,
 }
 
-fn find_and_resolve_property<T>(declarations: &[Declaration<'_>], property_name: &str, variables: &HashMap<String, String>) -> Option<T>
-where T: FromStr {
-  declarations
-    .iter()
-    .find(|dec| dec.name == property_name)
-    .map(|dec| {
-      if dec.value.starts_with("var(") && dec.value.ends_with(')') {
-        let last = dec.value.len() - 1;
-        let var = dec.value.get(4..last).unwrap();
-        if let Some(value) = variables.get(var) {
-          return value.parse();
-        }
+/// Main axis of a container: `row` or `column`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+  Row,
+  #[default]
+  Column,
+}
+
+/// Distribution of the free space along the main axis, when no child grows.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Justify {
+  #[default]
+  Start,
+  Center,
+  End,
+  SpaceBetween,
+}
+
+/// `none` hides the element. `block` and `flex` show it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Display {
+  #[default]
+  Block,
+  None,
+}
+
+/// What to do with text that doesn't fit: `clip` or `ellipsis`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TextOverflow {
+  #[default]
+  Clip,
+  Ellipsis,
+}
+
+/// `nowrap` or `wrap`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum WhiteSpace {
+  #[default]
+  NoWrap,
+  Wrap,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Easing {
+  #[default]
+  Linear,
+  Ease,
+  EaseIn,
+  EaseOut,
+  EaseInOut,
+}
+
+impl Easing {
+  /// Maps the progress `t`, from 0 to 1. Approximations of the CSS timing
+  /// functions.
+  pub fn apply(&self, t: f32) -> f32 {
+    match self {
+      Easing::Linear => t,
+      Easing::EaseIn => t * t,
+      Easing::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+      Easing::Ease | Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct Transition {
+  /// Property name, or `all`.
+  pub property: String,
+  /// In seconds.
+  pub duration: f32,
+  pub easing: Easing,
+}
+
+/// `transition: property duration [easing], …`, or `none`.
+#[derive(Debug, Default, Clone)]
+pub struct Transitions(pub Vec<Transition>);
+
+impl Transitions {
+  /// Like in CSS, the last matching transition wins.
+  pub fn get(&self, property: &str) -> Option<&Transition> {
+    self.0.iter().rev().find(|t| t.property == property || t.property == "all")
+  }
+
+  /// The longest duration, in seconds.
+  pub fn duration(&self) -> f32 {
+    self.0.iter().map(|t| t.duration).fold(0.0, f32::max)
+  }
+}
+
+// A declared value. `inherit` takes the value of the parent element.
+#[derive(Debug, Clone)]
+pub(crate) enum Value<T> {
+  Set(T),
+  Inherit,
+}
+
+fn find_and_resolve_property<T>(
+  declarations: &[Declaration<'_>],
+  property_name: &str,
+  variables: &Variables,
+  diagnostics: &mut Diagnostics<'_>,
+) -> Option<Value<T>>
+where
+  T: FromStr,
+{
+  let dec = declarations.iter().find(|dec| dec.name == property_name)?;
+  let value = variables.resolve(&tokenizer::strip_comments(dec.value)).and_then(|value| {
+    if value.trim() == "inherit" {
+      return Ok(Value::Inherit);
+    }
+    let value = functions::evaluate(&value)?;
+    value.parse().map(Value::Set).ok().ok_or_else(|| {
+      if value == dec.value {
+        anyhow!("Invalid value")
+      } else {
+        anyhow!("Invalid value: {}", value)
       }
-      dec.value.parse()
-    })
-    .transpose()
-    .unwrap_or_else(|_| {
-      warn!("Can't parse `{}` value", property_name);
-      None
     })
+  });
+  value
+    .map_err(|e| diagnostics.report(Severity::Error, dec.value, Some(dec.name), e.to_string()))
+    .ok()
 }
 
 macro_rules! prop_type {
   ($vis:vis struct $name:ident {
-    $($field:ident($key:expr) : $type:ty = $value:expr),* $(,)?
+    $($field:ident($key:expr, $inherited:expr) : $type:ty = $value:expr),* $(,)?
+  }
+  spacing {
+    $($sfield:ident($skey:expr, $sinherited:expr) : $stype:ty = $svalue:expr),* $(,)?
   }) => {
 
     #[derive(Debug, Clone)]
     pub struct ComputedProperties {
       $(
-        pub $field: $type
-      ),*
+        pub $field: $type,
+      )*
+      $(
+        pub $sfield: $stype,
+      )*
     }
 
     impl Default for ComputedProperties {
       fn default() -> Self {
         Self {
           $(
-            $field: $value
-          ),*
+            $field: $value,
+          )*
+          $(
+            $sfield: $svalue,
+          )*
         }
       }
     }
 
     impl ComputedProperties {
-      pub fn patch_from(&mut self, props: &OptionalProperties) {
+      /// Default values, except for the inherited properties.
+      pub fn inherited_from(parent: &ComputedProperties) -> Self {
+        Self {
+          $(
+            $field: if $inherited { parent.$field.clone() } else { $value },
+          )*
+          $(
+            $sfield: if $sinherited { parent.$sfield.clone() } else { $svalue },
+          )*
+        }
+      }
+
+      pub fn patch_from(&mut self, props: &OptionalProperties, parent: &ComputedProperties) {
+        $(
+          match &props.$field {
+            Some(Value::Set(v)) => self.$field = v.clone(),
+            Some(Value::Inherit) => self.$field = parent.$field.clone(),
+            None => {},
+          }
+        )*
         $(
-          if let Some(v) = props.$field { self.$field = v; }
+          match &props.$sfield {
+            Some(Value::Set(v)) => self.$sfield = v.clone(),
+            Some(Value::Inherit) => self.$sfield = parent.$sfield.clone(),
+            None => {},
+          }
         )*
       }
+
+      /// Names and values of the properties, for debugging. The native widgets
+      /// sizes are left out, see [ComputedProperties::spacing_fields].
+      pub fn fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+          $(
+            ($key, format!("{:?}", self.$field))
+          ),*
+        ]
+      }
+
+      /// Names and values of the native widgets sizes, which only matter on
+      /// the `spacing` element.
+      pub fn spacing_fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+          $(
+            ($skey, format!("{:?}", self.$sfield))
+          ),*
+        ]
+      }
     }
 
 
-    #[derive(Default)]
+    #[derive(Default, Clone)]
     $vis struct OptionalProperties {
       $(
-        $field: Option<$type>
-      ),*
+        $field: Option<Value<$type>>,
+      )*
+      $(
+        $sfield: Option<Value<$stype>>,
+      )*
     }
 
-    pub fn parse_declarations(decs: &[Declaration<'_>], vars: &HashMap<String, String>) -> OptionalProperties {
+    impl OptionalProperties {
+      pub(crate) fn names(&self) -> Vec<&'static str> {
+        let mut names = Vec::new();
+        $(
+          if self.$field.is_some() {
+            names.push($key);
+          }
+        )*
+        $(
+          if self.$sfield.is_some() {
+            names.push($skey);
+          }
+        )*
+        names
+      }
+    }
+
+    pub(crate) fn parse_declarations(decs: &[Declaration<'_>], vars: &Variables, diagnostics: &mut Diagnostics<'_>) -> OptionalProperties {
       for dec in decs {
-        if ($( dec.name != $key &&)* true) {
-          warn!("Unknown property: {}", dec.name);
+        if ($( dec.name != $key &&)* $( dec.name != $skey &&)* true) {
+          diagnostics.report(Severity::Warning, dec.name, Some(dec.name), "Unknown property".into());
         }
       }
       OptionalProperties {
         $(
-          $field: find_and_resolve_property(decs, $key, vars),
+          $field: find_and_resolve_property(decs, $key, vars, diagnostics),
+        )*
+        $(
+          $sfield: find_and_resolve_property(decs, $skey, vars, diagnostics),
         )*
       }
     }
@@ -114,32 +363,69 @@ macro_rules! prop_type {
 
 const INVALID_COLOR: Color = Color { r: 255, g: 0, b: 255, a: 1.0 };
 
+// The boolean marks the properties inherited from the parent element.
 prop_type! {
   pub struct Properties {
-    font_size("font-size"): f32 = 4.0,
-    font_family("font-family"): FontFamily = FontFamily::default(),
-    color("color"): Color = INVALID_COLOR,
-    background("background"): Color = INVALID_COLOR,
-    italics("italics"): bool = false,
-    underline_width("underline-width"): f32 = 0.0,
-    underline_color("underline-color"): Color = INVALID_COLOR,
-    strikethrough_width("strikethrough-width"): f32 = 0.0,
-    strikethrough_color("strikethrough-color"): Color = INVALID_COLOR,
-    align("align"): Align = Align::Min,
-    cross_align("cross-align"): Align = Align::Min,
-    padding("padding"): Sides = Sides::default(),
-    margin("margin"): Sides = Sides::default(),
-    radius("radius"): Radius = Radius::default(),
-    height("height"): f32 = 0.0,
-    width("width"): f32 = 0.0,
-    min_width("min-width"): f32 = 0.0,
-    max_width("max-width"): f32 = f32::INFINITY,
-    min_height("min-height"): f32 = 0.0,
-    max_height("max-height"): f32 = f32::INFINITY,
-    border_width("border-width"): f32 = 0.0,
-    border_color("border-color"): Color = INVALID_COLOR,
-    stroke_width("stroke-width"): f32 = 0.0,
-    stroke_color("stroke-color"): Color = INVALID_COLOR,
-    expansion("expansion"): f32 = 0.0,
+    font_size("font-size", true): Length = Length::Px(4.0),
+    font_family("font-family", true): FontFamily = FontFamily::default(),
+    color("color", true): Color = INVALID_COLOR,
+    background("background", false): Color = INVALID_COLOR,
+    italics("italics", true): bool = false,
+    white_space("white-space", true): WhiteSpace = WhiteSpace::default(),
+    // 0 uses the height of the font.
+    line_height("line-height", true): Length = Length::Px(0.0),
+    text_overflow("text-overflow", false): TextOverflow = TextOverflow::default(),
+    underline_width("underline-width", false): Length = Length::Px(0.0),
+    underline_color("underline-color", false): Color = INVALID_COLOR,
+    strikethrough_width("strikethrough-width", false): Length = Length::Px(0.0),
+    strikethrough_color("strikethrough-color", false): Color = INVALID_COLOR,
+    align("align", false): Align = Align::Min,
+    cross_align("cross-align", false): Align = Align::Min,
+    padding("padding", false): Sides = Sides::default(),
+    margin("margin", false): Sides = Sides::default(),
+    radius("radius", false): Radius = Radius::default(),
+    height("height", false): Length = Length::Px(0.0),
+    width("width", false): Length = Length::Px(0.0),
+    min_width("min-width", false): Length = Length::Px(0.0),
+    max_width("max-width", false): Length = Length::Px(f32::INFINITY),
+    min_height("min-height", false): Length = Length::Px(0.0),
+    max_height("max-height", false): Length = Length::Px(f32::INFINITY),
+    border_width("border-width", false): Length = Length::Px(0.0),
+    border_color("border-color", false): Color = INVALID_COLOR,
+    stroke_width("stroke-width", false): Length = Length::Px(0.0),
+    stroke_color("stroke-color", false): Color = INVALID_COLOR,
+    expansion("expansion", false): Length = Length::Px(0.0),
+    box_shadow("box-shadow", false): BoxShadow = BoxShadow::default(),
+    transition("transition", false): Transitions = Transitions::default(),
+    display("display", false): Display = Display::default(),
+    direction("direction", false): Direction = Direction::default(),
+    gap("gap", false): Length = Length::Px(0.0),
+    justify_content("justify-content", false): Justify = Justify::default(),
+    flex_grow("flex-grow", false): f32 = 0.0,
+  }
+  // Native widgets sizes, used on the `spacing` element. They are never
+  // animated.
+  spacing {
+    item_spacing("item-spacing", false): Size = Size::px(8.0, 3.0),
+    button_padding("button-padding", false): Size = Size::px(4.0, 1.0),
+    interact_size("interact-size", false): Size = Size::px(40.0, 18.0),
+    window_margin("window-margin", false): Sides = Sides::default(),
+    menu_margin("menu-margin", false): Sides = Sides::default(),
+    indent("indent", false): Length = Length::Px(18.0),
+    indent_ends_with_horizontal_line("indent-ends-with-horizontal-line", false): bool = false,
+    slider_width("slider-width", false): Length = Length::Px(100.0),
+    text_edit_width("text-edit-width", false): Length = Length::Px(280.0),
+    icon_width("icon-width", false): Length = Length::Px(14.0),
+    icon_width_inner("icon-width-inner", false): Length = Length::Px(8.0),
+    icon_spacing("icon-spacing", false): Length = Length::Px(4.0),
+    tooltip_width("tooltip-width", false): Length = Length::Px(600.0),
+    combo_height("combo-height", false): Length = Length::Px(200.0),
+    scroll_bar_width("scroll-bar-width", false): Length = Length::Px(8.0),
+    resize_grab_radius_side("resize-grab-radius-side", false): Length = Length::Px(5.0),
+    resize_grab_radius_corner("resize-grab-radius-corner", false): Length = Length::Px(10.0),
+    show_tooltips_only_when_still("show-tooltips-only-when-still", false): bool = true,
+    resize_corner_size("resize-corner-size", false): Length = Length::Px(12.0),
+    text_cursor_width("text-cursor-width", false): Length = Length::Px(2.0),
+    clip_rect_margin("clip-rect-margin", false): Length = Length::Px(3.0),
   }
 }
@@ -1,37 +1,336 @@
-use log::warn;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use log::trace;
 use ouroboros::self_referencing;
-use simplecss::{Element, Selector};
 
-use crate::properties::{ComputedProperties, OptionalProperties};
+use crate::font_face::FontFace;
+use crate::media::{Environment, MediaQuery};
+use crate::parser::Stylesheet;
+use crate::properties::{ComputedProperties, Length, OptionalProperties};
+use crate::selector::{AttributeOperator, Element, PseudoClass, Selector};
+use crate::variables::Variables;
 
+/// A selector and its declarations. See [Rules::explain].
 pub struct Rule<'a> {
   pub(crate) selector: Selector<'a>,
+  pub(crate) specificity: [u8; 3],
+  // Position in the stylesheets.
+  pub(crate) order: usize,
+  // Stylesheet origin, and 1-based line of the selectors.
+  pub(crate) origin: &'a str,
+  pub(crate) line: usize,
   pub(crate) properties: OptionalProperties,
+  // `!important` declarations.
+  pub(crate) important: OptionalProperties,
+  pub(crate) media: Option<MediaQuery>,
+  // Whether `media` matches the environment.
+  pub(crate) active: bool,
+}
+
+impl Rule<'_> {
+  pub fn selector(&self) -> String {
+    self.selector.to_string()
+  }
+
+  pub fn specificity(&self) -> [u8; 3] {
+    self.specificity
+  }
+
+  /// See [Stylesheet](crate::Stylesheet).
+  pub fn origin(&self) -> &str {
+    self.origin
+  }
+
+  pub fn line(&self) -> usize {
+    self.line
+  }
+
+  /// Names of the properties the rule sets.
+  pub fn properties(&self) -> Vec<&'static str> {
+    self.properties.names()
+  }
+
+  /// Names of the properties the rule sets with `!important`.
+  pub fn important_properties(&self) -> Vec<&'static str> {
+    self.important.names()
+  }
+}
+
+// The root element, to which `rem` lengths are relative.
+struct RootElement;
+
+impl std::fmt::Display for RootElement {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "native")
+  }
+}
+
+impl Element for RootElement {
+  fn has_local_name(&self, name: &str) -> bool {
+    name == "native"
+  }
+
+  fn attribute_matches(&self, _attr: &str, _operator: AttributeOperator<'_>) -> bool {
+    false
+  }
+
+  fn parent_element(&self) -> Option<Self> {
+    None
+  }
+
+  fn prev_sibling_element(&self) -> Option<Self> {
+    None
+  }
+
+  fn pseudo_class_matches(&self, _class: PseudoClass) -> bool {
+    false
+  }
 }
 
 // We use ouroboros because handling lifetimes for rules was becoming difficult.
 // A self referencing struct solves the issue.
 #[self_referencing(pub_extras)]
 pub struct Rules {
-  source: String,
-  #[borrows(source)]
+  stylesheets: Vec<Stylesheet>,
+  // Font-size of the `native` element. Used to resolve `rem` lengths.
+  root_font_size: f32,
+  generation: u64,
+  environment: Environment,
+  // `@font-face` declarations, in source order.
+  font_faces: Vec<FontFace>,
+  // Resolved for the system classes. Used by the serializer.
+  variables: Variables,
+  #[borrows(stylesheets)]
   #[covariant]
   rules: Vec<Rule<'this>>,
 }
 
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+pub(crate) fn next_generation() -> u64 {
+  GENERATION.fetch_add(1, Ordering::Relaxed)
+}
+
 impl Rules {
+  pub fn root_font_size(&self) -> f32 {
+    *self.borrow_root_font_size()
+  }
+
+  /// Unique to each parsed set of rules. Results computed from other rules
+  /// are stale.
+  pub fn generation(&self) -> u64 {
+    *self.borrow_generation()
+  }
+
+  pub fn environment(&self) -> &Environment {
+    self.borrow_environment()
+  }
+
+  pub fn font_faces(&self) -> &[FontFace] {
+    self.borrow_font_faces()
+  }
+
+  /// The stylesheets the rules were parsed from.
+  pub fn stylesheets(&self) -> &[Stylesheet] {
+    self.borrow_stylesheets()
+  }
+
+  /// All the rules, in source order.
+  pub(crate) fn rules(&self) -> &[Rule<'_>] {
+    self.borrow_rules()
+  }
+
+  pub(crate) fn variables(&self) -> &Variables {
+    self.borrow_variables()
+  }
+
+  /// Enables the `@media` blocks matching `environment`. Returns true if that
+  /// changed any rule, in which case the generation changes too.
+  pub fn set_environment(&mut self, environment: Environment) -> bool {
+    let changed = self.with_rules_mut(|rules| {
+      let mut changed = false;
+      for rule in rules.iter_mut() {
+        let active = rule.media.as_ref().map_or(true, |media| media.matches(&environment));
+        changed |= active != rule.active;
+        rule.active = active;
+      }
+      changed
+    });
+    self.with_environment_mut(|env| *env = environment);
+    if changed {
+      self.with_generation_mut(|generation| *generation = next_generation());
+      self.update_root_font_size();
+    }
+    changed
+  }
+
+  pub(crate) fn set_font_faces(&mut self, font_faces: Vec<FontFace>) {
+    self.with_font_faces_mut(|faces| *faces = font_faces);
+  }
+
+  pub(crate) fn set_variables(&mut self, variables: Variables) {
+    self.with_variables_mut(|vars| *vars = variables);
+  }
+
+  pub(crate) fn update_root_font_size(&mut self) {
+    let font_size = ComputedProperties::default().font_size.resolve(0.0, 0.0, 0.0);
+    let root_font_size = self.solve(&RootElement).font_size.resolve(font_size, font_size, font_size);
+    self.with_root_font_size_mut(|size| *size = root_font_size);
+  }
+
+  /// Solves the parent elements first, to inherit their properties.
   pub fn solve<E: Element + std::fmt::Display>(&self, element: &E) -> ComputedProperties {
-    let mut prop = ComputedProperties::default();
-    let mut matched = false;
-    for rule in self.borrow_rules().iter() {
-      if rule.selector.matches(element) {
-        matched = true;
-        prop.patch_from(&rule.properties);
+    let parent = element.parent_element().map(|parent| self.solve(&parent));
+    self.solve_with_parent(element, parent.as_ref())
+  }
+
+  /// The structural pseudo-classes used by the rules, without duplicates.
+  pub fn structural_pseudo_classes(&self) -> Vec<PseudoClass> {
+    let mut classes = Vec::new();
+    for rule in self.rules() {
+      for class in rule.selector.structural_pseudo_classes() {
+        if !classes.contains(&class) {
+          classes.push(class);
+        }
       }
     }
-    if !matched {
-      warn!("Couldn't match any rules for element: {}", element);
+    classes
+  }
+
+  /// The rules matching `element`, in the order they are applied.
+  pub fn explain<E: Element>(&self, element: &E) -> Vec<&Rule<'_>> {
+    let mut matched: Vec<&Rule<'_>> = self.rules().iter().filter(|rule| rule.active && rule.selector.matches(element)).collect();
+    matched.sort_by_key(|rule| (rule.specificity, rule.order));
+    matched
+  }
+
+  // Like in CSS, more specific rules win, then the last one in source order.
+  // `!important` declarations win over the other ones.
+  pub fn solve_with_parent<E: Element + std::fmt::Display>(&self, element: &E, parent: Option<&ComputedProperties>) -> ComputedProperties {
+    let matched = self.explain(element);
+    if matched.is_empty() {
+      trace!("Couldn't match any rules for element: {}", element);
     }
+
+    let default = ComputedProperties::default();
+    let parent = parent.unwrap_or(&default);
+    let mut prop = ComputedProperties::inherited_from(parent);
+    for rule in &matched {
+      prop.patch_from(&rule.properties, parent);
+    }
+    for rule in &matched {
+      prop.patch_from(&rule.important, parent);
+    }
+
+    // Children inherit the computed font-size, not the relative one.
+    let root_font_size = self.root_font_size();
+    let parent_font_size = parent.font_size.resolve(root_font_size, root_font_size, root_font_size);
+    prop.font_size = match prop.font_size {
+      Length::Em(_) | Length::Percent(_) => Length::Px(prop.font_size.resolve(parent_font_size, root_font_size, parent_font_size)),
+      font_size => font_size,
+    };
     prop
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::parser::parse_css_str;
+  use crate::testing::TestElement;
+
+  fn width(source: &str) -> Length {
+    let (rules, diagnostics) = parse_css_str(source, &[]);
+    assert!(diagnostics.is_empty());
+    rules.solve(&TestElement::new("button.big")).width
+  }
+
+  #[test]
+  fn specificity() {
+    assert_eq!(width("button.big { width: 1 } button { width: 2 }"), Length::Px(1.0));
+    assert_eq!(width(".big { width: 1 } button { width: 2 }"), Length::Px(1.0));
+    assert_eq!(width("* { width: 1 } button { width: 2 } * { width: 3 }"), Length::Px(2.0));
+    let (rules, _) = parse_css_str("button.big { width: 1 } button { width: 2 } * { width: 3 }", &[]);
+    let specificities: Vec<[u8; 3]> = rules.explain(&TestElement::new("button.big")).iter().map(|rule| rule.specificity()).collect();
+    assert_eq!(specificities, [[0, 0, 0], [0, 0, 1], [0, 1, 1]]);
+  }
+
+  #[test]
+  fn source_order() {
+    assert_eq!(width("button { width: 1 } button { width: 2 }"), Length::Px(2.0));
+    assert_eq!(width(".big { width: 1 } .big { width: 2 } button { width: 3 }"), Length::Px(2.0));
+  }
+
+  #[test]
+  fn important() {
+    assert_eq!(width("button { width: 1 !important } button.big { width: 2 }"), Length::Px(1.0));
+    assert_eq!(width("button { width: 1 !important } button { width: 2 }"), Length::Px(1.0));
+    assert_eq!(width("button { width: 1 !important } button.big { width: 2 !important }"), Length::Px(2.0));
+    assert_eq!(width("button.big { width: 1 !important } button { width: 2 !important }"), Length::Px(1.0));
+    assert_eq!(width("button { width: 1 !important } button { width: 2 !important }"), Length::Px(2.0));
+  }
+
+  // The properties of `selector`, its parents solved first.
+  fn solve(source: &str, selector: &str) -> ComputedProperties {
+    let (rules, diagnostics) = parse_css_str(source, &[]);
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    rules.solve(&TestElement::new(selector))
+  }
+
+  fn rgb(color: crate::properties::Color) -> (u8, u8, u8) {
+    (color.r, color.g, color.b)
+  }
+
+  const PARENT: &str = "vbox { color: #f00; background: #00f; padding: 3; font-size: 20; }";
+
+  #[test]
+  fn inheritance() {
+    let label = solve(PARENT, "vbox > label");
+    assert_eq!(rgb(label.color), (255, 0, 0));
+    assert_eq!(label.font_size, Length::Px(20.0));
+    // Through several levels.
+    assert_eq!(rgb(solve(PARENT, "vbox > hbox > label").color), (255, 0, 0));
+    // Overridden by the child.
+    assert_eq!(rgb(solve(&format!("{} label {{ color: #0f0; }}", PARENT), "vbox > label").color), (0, 255, 0));
+  }
+
+  #[test]
+  fn not_inherited() {
+    let default = ComputedProperties::default();
+    let label = solve(PARENT, "vbox > label");
+    assert_eq!(rgb(label.background), rgb(default.background));
+    assert_eq!(format!("{:?}", label.padding), format!("{:?}", default.padding));
+  }
+
+  #[test]
+  fn inherit_keyword() {
+    let source = format!(
+      "{} label {{ background: inherit; padding: inherit; color: #0f0; }} hbox {{ color: inherit; }}",
+      PARENT
+    );
+    let label = solve(&source, "vbox > label");
+    assert_eq!(rgb(label.background), (0, 0, 255));
+    assert_eq!(format!("{:?}", label.padding), format!("{:?}", solve(PARENT, "vbox").padding));
+    assert_eq!(rgb(label.color), (0, 255, 0));
+    assert_eq!(rgb(solve(&source, "vbox > hbox").color), (255, 0, 0));
+    // Without parent, the default value.
+    assert_eq!(rgb(solve(&source, "label").background), rgb(ComputedProperties::default().background));
+  }
+
+  #[test]
+  fn relative_font_size() {
+    let font_size = |source: &str, selector: &str| solve(&format!("{} {}", PARENT, source), selector).font_size;
+    assert_eq!(font_size("label { font-size: 1.5em; }", "vbox > label"), Length::Px(30.0));
+    assert_eq!(font_size("label { font-size: 50%; }", "vbox > label"), Length::Px(10.0));
+    // Children inherit the computed size, not the relative one.
+    assert_eq!(
+      font_size("hbox { font-size: 0.5em; } label { font-size: 2em; }", "vbox > hbox > label"),
+      Length::Px(20.0)
+    );
+    assert_eq!(font_size("hbox { font-size: 0.5em; }", "vbox > hbox > label"), Length::Px(10.0));
+    assert_eq!(
+      font_size("native { font-size: 8; } label { font-size: 2rem; }", "vbox > label"),
+      Length::Rem(2.0)
+    );
+  }
+}
@@ -0,0 +1,536 @@
+//! Selectors and the elements they match.
+//! Modeled after simplecss' selectors, which don't support the structural
+//! pseudo-classes.
+//!
+//! Supported: `*`, `local`, `#id`, `.class`, `[attr]`, `[attr=value]`,
+//! `[attr~=value]`, `[attr|=value]`, the ` `, `>` and `+` combinators, and the
+//! `:hover`, `:active`, `:focus`, `:first-child`, `:last-child`,
+//! `:nth-child(an+b)` and `:nth-last-child(an+b)` pseudo-classes.
+
+use std::fmt;
+
+pub use simplecss::AttributeOperator;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PseudoClass {
+  Hover,
+  Active,
+  Focus,
+  FirstChild,
+  LastChild,
+  /// `:nth-child(an+b)`, with `a` and `b`.
+  NthChild(i32, i32),
+  /// `:nth-last-child(an+b)`, with `a` and `b`.
+  NthLastChild(i32, i32),
+}
+
+impl PseudoClass {
+  /// Whether the pseudo-class depends on the position among siblings.
+  pub fn is_structural(&self) -> bool {
+    !matches!(self, PseudoClass::Hover | PseudoClass::Active | PseudoClass::Focus)
+  }
+
+  /// For structural pseudo-classes, whether the element at `index` (0-based)
+  /// among `count` siblings matches. Always false for the other ones.
+  pub fn matches_position(&self, index: usize, count: usize) -> bool {
+    let position = index as i32 + 1;
+    let position_from_end = count as i32 - index as i32;
+    match *self {
+      PseudoClass::FirstChild => position == 1,
+      PseudoClass::LastChild => position_from_end == 1,
+      PseudoClass::NthChild(a, b) => matches_nth(a, b, position),
+      PseudoClass::NthLastChild(a, b) => matches_nth(a, b, position_from_end),
+      PseudoClass::Hover | PseudoClass::Active | PseudoClass::Focus => false,
+    }
+  }
+}
+
+// Whether `position` (1-based) is `a * n + b` for some n >= 0.
+fn matches_nth(a: i32, b: i32, position: i32) -> bool {
+  if a == 0 {
+    position == b
+  } else {
+    let n = position - b;
+    n % a == 0 && n / a >= 0
+  }
+}
+
+// Parses the `an+b` argument of `:nth-child()`.
+fn parse_nth(text: &str) -> Option<(i32, i32)> {
+  let text: String = text.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_lowercase();
+  match text.as_str() {
+    "odd" => return Some((2, 1)),
+    "even" => return Some((2, 0)),
+    _ => {},
+  }
+  let (a, b) = match text.split_once('n') {
+    Some(parts) => parts,
+    None => return Some((0, text.parse().ok()?)),
+  };
+  let a = match a {
+    "" | "+" => 1,
+    "-" => -1,
+    a => a.parse().ok()?,
+  };
+  let b = match b {
+    "" => 0,
+    b if b.starts_with('+') || b.starts_with('-') => b.parse().ok()?,
+    _ => return None,
+  };
+  Some((a, b))
+}
+
+fn write_nth(f: &mut fmt::Formatter<'_>, a: i32, b: i32) -> fmt::Result {
+  match (a, b) {
+    (0, b) => write!(f, "{}", b),
+    (a, 0) => write!(f, "{}n", a),
+    (a, b) => write!(f, "{}n{:+}", a, b),
+  }
+}
+
+impl fmt::Display for PseudoClass {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match *self {
+      PseudoClass::Hover => write!(f, "hover"),
+      PseudoClass::Active => write!(f, "active"),
+      PseudoClass::Focus => write!(f, "focus"),
+      PseudoClass::FirstChild => write!(f, "first-child"),
+      PseudoClass::LastChild => write!(f, "last-child"),
+      PseudoClass::NthChild(a, b) => {
+        write!(f, "nth-child(")?;
+        write_nth(f, a, b)?;
+        write!(f, ")")
+      },
+      PseudoClass::NthLastChild(a, b) => {
+        write!(f, "nth-last-child(")?;
+        write_nth(f, a, b)?;
+        write!(f, ")")
+      },
+    }
+  }
+}
+
+/// A node that can be matched against selectors.
+pub trait Element: Sized {
+  fn parent_element(&self) -> Option<Self>;
+
+  fn prev_sibling_element(&self) -> Option<Self>;
+
+  fn has_local_name(&self, name: &str) -> bool;
+
+  fn attribute_matches(&self, name: &str, operator: AttributeOperator<'_>) -> bool;
+
+  /// See [PseudoClass::matches_position] for the structural pseudo-classes.
+  fn pseudo_class_matches(&self, class: PseudoClass) -> bool;
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Combinator {
+  None,
+  Descendant,
+  Child,
+  AdjacentSibling,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum SubSelector<'a> {
+  Attribute(&'a str, AttributeOperator<'a>),
+  PseudoClass(PseudoClass),
+}
+
+#[derive(Clone, Debug)]
+struct Component<'a> {
+  // Combinator with the previous component.
+  combinator: Combinator,
+  // `None` for `*`.
+  local: Option<&'a str>,
+  subselectors: Vec<SubSelector<'a>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Selector<'a> {
+  components: Vec<Component<'a>>,
+}
+
+impl<'a> Selector<'a> {
+  /// `None` if the selector is invalid or not supported.
+  pub fn parse(text: &'a str) -> Option<Self> {
+    let mut parser = Parser { text, pos: 0 };
+    let mut components: Vec<Component<'a>> = Vec::new();
+    let mut combinator = Combinator::None;
+    loop {
+      let whitespace = parser.skip_whitespace();
+      let c = match parser.peek() {
+        Some(c) => c,
+        None => break,
+      };
+      let explicit = match c {
+        '>' => Some(Combinator::Child),
+        '+' => Some(Combinator::AdjacentSibling),
+        _ => None,
+      };
+      if let Some(explicit) = explicit {
+        if components.is_empty() || combinator != Combinator::None {
+          return None;
+        }
+        combinator = explicit;
+        parser.pos += 1;
+        continue;
+      }
+      if whitespace && !components.is_empty() && combinator == Combinator::None {
+        combinator = Combinator::Descendant;
+      } else if !components.is_empty() && combinator == Combinator::None {
+        return None;
+      }
+      components.push(parser.component(combinator)?);
+      combinator = Combinator::None;
+    }
+    if components.is_empty() || combinator != Combinator::None {
+      return None;
+    }
+    Some(Selector { components })
+  }
+
+  /// Local names the matched element and its ancestors or siblings must have.
+  pub(crate) fn local_names(&self) -> impl Iterator<Item = &'a str> + '_ {
+    self.components.iter().filter_map(|component| component.local)
+  }
+
+  /// Pseudo-classes whose match depends on the position among siblings.
+  pub(crate) fn structural_pseudo_classes(&self) -> impl Iterator<Item = PseudoClass> + '_ {
+    self.components.iter().flat_map(|component| &component.subselectors).filter_map(|sub| {
+      match *sub {
+        SubSelector::PseudoClass(class) if class.is_structural() => Some(class),
+        _ => None,
+      }
+    })
+  }
+
+  /// Ids, then classes, attributes and pseudo-classes, then local names.
+  pub fn specificity(&self) -> [u8; 3] {
+    let mut spec = [0u8; 3];
+    for component in &self.components {
+      if component.local.is_some() {
+        spec[2] = spec[2].saturating_add(1);
+      }
+      for sub in &component.subselectors {
+        match sub {
+          SubSelector::Attribute("id", _) => spec[0] = spec[0].saturating_add(1),
+          _ => spec[1] = spec[1].saturating_add(1),
+        }
+      }
+    }
+    spec
+  }
+
+  pub fn matches<E: Element>(&self, element: &E) -> bool {
+    self.matches_component(self.components.len() - 1, element)
+  }
+
+  fn matches_component<E: Element>(&self, index: usize, element: &E) -> bool {
+    let component = &self.components[index];
+    if !component.matches(element) {
+      return false;
+    }
+    match component.combinator {
+      Combinator::None => true,
+      Combinator::Descendant => {
+        let mut parent = element.parent_element();
+        while let Some(elt) = parent {
+          if self.matches_component(index - 1, &elt) {
+            return true;
+          }
+          parent = elt.parent_element();
+        }
+        false
+      },
+      Combinator::Child => element.parent_element().map_or(false, |elt| self.matches_component(index - 1, &elt)),
+      Combinator::AdjacentSibling => element.prev_sibling_element().map_or(false, |elt| self.matches_component(index - 1, &elt)),
+    }
+  }
+}
+
+impl<'a> Component<'a> {
+  fn matches<E: Element>(&self, element: &E) -> bool {
+    if let Some(local) = self.local {
+      if !element.has_local_name(local) {
+        return false;
+      }
+    }
+    self.subselectors.iter().all(|sub| {
+      match *sub {
+        SubSelector::Attribute(name, operator) => element.attribute_matches(name, operator),
+        SubSelector::PseudoClass(class) => element.pseudo_class_matches(class),
+      }
+    })
+  }
+}
+
+impl fmt::Display for Selector<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for component in &self.components {
+      match component.combinator {
+        Combinator::None => {},
+        Combinator::Descendant => write!(f, " ")?,
+        Combinator::Child => write!(f, " > ")?,
+        Combinator::AdjacentSibling => write!(f, " + ")?,
+      }
+      match component.local {
+        Some(local) => write!(f, "{}", local)?,
+        None if component.subselectors.is_empty() => write!(f, "*")?,
+        None => {},
+      }
+      for sub in &component.subselectors {
+        match sub {
+          SubSelector::Attribute("class", AttributeOperator::Contains(class)) => write!(f, ".{}", class)?,
+          SubSelector::Attribute("id", AttributeOperator::Matches(id)) => write!(f, "#{}", id)?,
+          SubSelector::Attribute(name, AttributeOperator::Exists) => write!(f, "[{}]", name)?,
+          SubSelector::Attribute(name, AttributeOperator::Matches(value)) => write!(f, "[{}='{}']", name, value)?,
+          SubSelector::Attribute(name, AttributeOperator::Contains(value)) => write!(f, "[{}~='{}']", name, value)?,
+          SubSelector::Attribute(name, AttributeOperator::StartsWith(value)) => write!(f, "[{}|='{}']", name, value)?,
+          SubSelector::PseudoClass(class) => write!(f, ":{}", class)?,
+        }
+      }
+    }
+    Ok(())
+  }
+}
+
+struct Parser<'a> {
+  text: &'a str,
+  pos: usize,
+}
+
+impl<'a> Parser<'a> {
+  fn peek(&self) -> Option<char> {
+    self.text[self.pos..].chars().next()
+  }
+
+  fn eat(&mut self, c: char) -> bool {
+    if self.peek() == Some(c) {
+      self.pos += c.len_utf8();
+      true
+    } else {
+      false
+    }
+  }
+
+  fn skip_whitespace(&mut self) -> bool {
+    let start = self.pos;
+    while let Some(c) = self.peek().filter(|c| c.is_whitespace()) {
+      self.pos += c.len_utf8();
+    }
+    self.pos != start
+  }
+
+  fn ident(&mut self) -> Option<&'a str> {
+    let rest = &self.text[self.pos..];
+    let len = rest.find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_')).unwrap_or(rest.len());
+    if len == 0 {
+      return None;
+    }
+    self.pos += len;
+    Some(&rest[..len])
+  }
+
+  // Text up to `end`, which is consumed.
+  fn until(&mut self, end: char) -> Option<&'a str> {
+    let rest = &self.text[self.pos..];
+    let len = rest.find(end)?;
+    self.pos += len + end.len_utf8();
+    Some(&rest[..len])
+  }
+
+  fn component(&mut self, combinator: Combinator) -> Option<Component<'a>> {
+    let universal = self.eat('*');
+    let local = if universal { None } else { self.ident() };
+    let mut subselectors = Vec::new();
+    loop {
+      let sub = match self.peek() {
+        Some('.') => {
+          self.pos += 1;
+          SubSelector::Attribute("class", AttributeOperator::Contains(self.ident()?))
+        },
+        Some('#') => {
+          self.pos += 1;
+          SubSelector::Attribute("id", AttributeOperator::Matches(self.ident()?))
+        },
+        Some('[') => {
+          self.pos += 1;
+          self.attribute()?
+        },
+        Some(':') => {
+          self.pos += 1;
+          SubSelector::PseudoClass(self.pseudo_class()?)
+        },
+        _ => break,
+      };
+      subselectors.push(sub);
+    }
+    if !universal && local.is_none() && subselectors.is_empty() {
+      return None;
+    }
+    Some(Component {
+      combinator,
+      local,
+      subselectors,
+    })
+  }
+
+  fn attribute(&mut self) -> Option<SubSelector<'a>> {
+    self.skip_whitespace();
+    let name = self.ident()?;
+    self.skip_whitespace();
+    if self.eat(']') {
+      return Some(SubSelector::Attribute(name, AttributeOperator::Exists));
+    }
+    let operator: fn(&'a str) -> AttributeOperator<'a> = if self.eat('=') {
+      AttributeOperator::Matches
+    } else if self.eat('~') && self.eat('=') {
+      AttributeOperator::Contains
+    } else if self.eat('|') && self.eat('=') {
+      AttributeOperator::StartsWith
+    } else {
+      return None;
+    };
+    self.skip_whitespace();
+    let value = match self.peek() {
+      Some(quote @ ('"' | '\'')) => {
+        self.pos += 1;
+        self.until(quote)?
+      },
+      _ => self.ident()?,
+    };
+    self.skip_whitespace();
+    if !self.eat(']') {
+      return None;
+    }
+    Some(SubSelector::Attribute(name, operator(value)))
+  }
+
+  fn pseudo_class(&mut self) -> Option<PseudoClass> {
+    let name = self.ident()?;
+    let argument = if self.eat('(') { Some(self.until(')')?) } else { None };
+    match (name, argument) {
+      ("hover", None) => Some(PseudoClass::Hover),
+      ("active", None) => Some(PseudoClass::Active),
+      ("focus", None) => Some(PseudoClass::Focus),
+      ("first-child", None) => Some(PseudoClass::FirstChild),
+      ("last-child", None) => Some(PseudoClass::LastChild),
+      ("nth-child", Some(argument)) => parse_nth(argument).map(|(a, b)| PseudoClass::NthChild(a, b)),
+      ("nth-last-child", Some(argument)) => parse_nth(argument).map(|(a, b)| PseudoClass::NthLastChild(a, b)),
+      _ => None,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::testing::TestElement;
+
+  // Positions (1-based, up to 10) matched by `an+b`.
+  fn matched(text: &str) -> Vec<i32> {
+    let (a, b) = parse_nth(text).unwrap();
+    (1..=10).filter(|&position| matches_nth(a, b, position)).collect()
+  }
+
+  #[test]
+  fn parse() {
+    assert_eq!(parse_nth("odd"), Some((2, 1)));
+    assert_eq!(parse_nth(" EVEN "), Some((2, 0)));
+    assert_eq!(parse_nth("2n+1"), Some((2, 1)));
+    assert_eq!(parse_nth("2n + 1"), Some((2, 1)));
+    assert_eq!(parse_nth("-n+3"), Some((-1, 3)));
+    assert_eq!(parse_nth("+n-1"), Some((1, -1)));
+    assert_eq!(parse_nth("n"), Some((1, 0)));
+    assert_eq!(parse_nth("3"), Some((0, 3)));
+    assert_eq!(parse_nth("3n"), Some((3, 0)));
+  }
+
+  #[test]
+  fn parse_invalid() {
+    for text in ["", "n1", "2x+1", "n+", "n+a", "2n+1n", "one", "--n"] {
+      assert_eq!(parse_nth(text), None, "{}", text);
+    }
+  }
+
+  fn selector(text: &str) -> Selector<'_> {
+    Selector::parse(text).unwrap_or_else(|| panic!("{}", text))
+  }
+
+  fn matches_element(selector: &str, element: &str) -> bool {
+    self::selector(selector).matches(&TestElement::new(element))
+  }
+
+  #[test]
+  fn parse_selectors() {
+    for text in [
+      "*",
+      "label",
+      "#id",
+      ".a.b",
+      "label#id.a:hover",
+      "vbox label",
+      "vbox > label",
+      "label + label",
+      "a b > c + d",
+      "label:nth-child(2n+1)",
+    ] {
+      assert_eq!(selector(text).to_string(), text);
+    }
+    assert_eq!(selector("vbox>label").to_string(), "vbox > label");
+    assert_eq!(selector("  vbox   label ").to_string(), "vbox label");
+    assert_eq!(selector(":nth-last-child( -n + 3 )").to_string(), ":nth-last-child(-1n+3)");
+    assert_eq!(selector(":nth-child(odd)").to_string(), ":nth-child(2n+1)");
+    for text in ["", ">", "> label", "label >", "label + > label", "label:nth-child(x)", "label:unknown"] {
+      assert!(Selector::parse(text).is_none(), "{}", text);
+    }
+  }
+
+  #[test]
+  fn specificity() {
+    assert_eq!(selector("*").specificity(), [0, 0, 0]);
+    assert_eq!(selector("label").specificity(), [0, 0, 1]);
+    assert_eq!(selector("vbox > label + label").specificity(), [0, 0, 3]);
+    assert_eq!(selector(".a.b:hover").specificity(), [0, 3, 0]);
+    assert_eq!(selector("label:nth-child(2n)").specificity(), [0, 1, 1]);
+    assert_eq!(selector("vbox #id.a label").specificity(), [1, 1, 2]);
+  }
+
+  #[test]
+  fn combinators() {
+    // Descendant.
+    assert!(matches_element("vbox label", "vbox > label"));
+    assert!(matches_element("vbox label", "vbox > hbox > label"));
+    assert!(!matches_element("vbox label", "hbox > label"));
+    assert!(!matches_element("vbox label", "label"));
+    // Child.
+    assert!(matches_element("vbox > label", "vbox > label"));
+    assert!(!matches_element("vbox > label", "vbox > hbox > label"));
+    assert!(matches_element("vbox > * > label", "vbox > hbox > label"));
+    // Adjacent sibling.
+    assert!(matches_element("label + button", "label + button"));
+    assert!(!matches_element("label + button", "hbox > button"));
+    assert!(!matches_element("label + button", "label + hbox + button"));
+    assert!(matches_element("label + hbox + button", "label + hbox + button"));
+    assert!(matches_element("vbox > label + button", "vbox > label + button"));
+    assert!(!matches_element("hbox > label + button", "vbox > label + button"));
+    // Compounds.
+    assert!(matches_element(".a > label#id.b:hover", "hbox.a > label#id.b.c:hover"));
+    assert!(!matches_element(".a > label#id.b:hover", "hbox.a > label#id.b"));
+  }
+
+  #[test]
+  fn matches() {
+    assert_eq!(matched("odd"), [1, 3, 5, 7, 9]);
+    assert_eq!(matched("even"), [2, 4, 6, 8, 10]);
+    assert_eq!(matched("2n+1"), [1, 3, 5, 7, 9]);
+    assert_eq!(matched("-n+3"), [1, 2, 3]);
+    assert_eq!(matched("n"), (1..=10).collect::<Vec<_>>());
+    assert_eq!(matched("3"), [3]);
+    assert_eq!(matched("3n-1"), [2, 5, 8]);
+    assert_eq!(matched("-2n+10"), [2, 4, 6, 8, 10]);
+    assert_eq!(matched("0n+0"), Vec::<i32>::new());
+  }
+}
@@ -0,0 +1,335 @@
+//! Writes [Rules] back as a normalized stylesheet: one declaration per line,
+//! two spaces indentation, and a blank line between blocks. Comments between
+//! blocks and declarations are kept.
+//!
+//! The stylesheets are concatenated. Whatever the parser ignores, like invalid
+//! declarations, unsupported selectors or unknown at-rules, is dropped.
+//! Parsing the output with the same system classes yields the same computed
+//! styles. With [Values::Resolved], the system classes don't matter anymore.
+
+use std::collections::HashSet;
+
+use crate::diagnostics::Diagnostics;
+use crate::functions;
+use crate::media::MediaQuery;
+use crate::parser::{is_variables_selector, parse_font_face, Stylesheet};
+use crate::rules::Rules;
+use crate::selector::Selector;
+use crate::tokenizer::{self, offset_in};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Values {
+  /// As written, `var()` and color functions included. The `variables`
+  /// blocks are kept.
+  Unresolved,
+  /// With the variables and the color functions evaluated. The `variables`
+  /// blocks are dropped.
+  Resolved,
+}
+
+pub fn serialize(rules: &Rules, values: Values) -> String {
+  let mut writer = Writer {
+    rules,
+    values,
+    out: String::new(),
+    after_comments: false,
+  };
+  for stylesheet in rules.stylesheets() {
+    writer.stylesheet(stylesheet);
+  }
+  writer.out
+}
+
+struct Writer<'a> {
+  rules: &'a Rules,
+  values: Values,
+  out: String,
+  // Comments stick to the next block.
+  after_comments: bool,
+}
+
+// Joins the lines of multi-line values.
+fn normalize(value: &str) -> String {
+  value.lines().map(str::trim).filter(|line| !line.is_empty()).collect::<Vec<_>>().join(" ")
+}
+
+impl Writer<'_> {
+  fn stylesheet(&mut self, stylesheet: &Stylesheet) {
+    let mut diagnostics = Diagnostics::new(&stylesheet.origin, &stylesheet.source);
+    self.blocks(stylesheet, &stylesheet.source, "", &mut diagnostics);
+  }
+
+  // Separates the blocks with a blank line.
+  fn separate(&mut self, indent: &str) {
+    if !self.out.is_empty() && !self.out.ends_with("{\n") && !self.after_comments {
+      self.out.push('\n');
+    }
+    self.after_comments = false;
+    self.out.push_str(indent);
+  }
+
+  fn comments(&mut self, text: &str, indent: &str) {
+    let comments = tokenizer::comments(text);
+    if comments.is_empty() {
+      return;
+    }
+    self.separate(indent);
+    self.out.push_str(&comments.join(&format!("\n{}", indent)));
+    self.out.push('\n');
+    self.after_comments = true;
+  }
+
+  fn blocks(&mut self, stylesheet: &Stylesheet, source: &str, indent: &str, diagnostics: &mut Diagnostics<'_>) {
+    let mut end = 0;
+    for block in tokenizer::blocks(source) {
+      let start = offset_in(source, block.prelude);
+      self.comments(&source[end.min(start)..start], indent);
+      end = match block.body {
+        Some(body) => offset_in(source, body) + body.len() + 1,
+        None => start + block.prelude.len(),
+      };
+      let body = match block.body {
+        Some(body) => body,
+        None => continue,
+      };
+
+      let media_query = block
+        .prelude
+        .strip_prefix("@media")
+        .filter(|query| query.starts_with(|c: char| c.is_whitespace() || c == '('));
+      if let Some(query) = media_query {
+        // Like the parser, skip the invalid or nested `@media` blocks.
+        if indent.is_empty() && query.parse::<MediaQuery>().is_ok() {
+          self.separate(indent);
+          self
+            .out
+            .push_str(&format!("@media {} {{\n", query.split_whitespace().collect::<Vec<_>>().join(" ")));
+          self.blocks(stylesheet, body, "  ", diagnostics);
+          self.out.push_str("}\n");
+        }
+      } else if block.prelude == "@font-face" {
+        if let (true, Some(face)) = (indent.is_empty(), parse_font_face(block.prelude, body, stylesheet.dir.as_deref(), diagnostics)) {
+          let sources: Vec<String> = face.sources.iter().map(|path| format!("url(\"{}\")", path.display())).collect();
+          self.separate(indent);
+          self.out.push_str("@font-face {\n");
+          self.out.push_str(&format!("  font-family: \"{}\";\n", face.family));
+          self.out.push_str(&format!("  src: {};\n", sources.join(", ")));
+          self.out.push_str("}\n");
+        }
+      } else if !block.prelude.starts_with('@') {
+        self.rule(block.prelude, body, indent);
+      }
+    }
+    self.comments(&source[end.min(source.len())..], indent);
+  }
+
+  fn rule(&mut self, prelude: &str, body: &str, indent: &str) {
+    let selectors: Vec<String> = tokenizer::split(prelude, ',')
+      .into_iter()
+      .filter_map(|text| Selector::parse(text.trim()))
+      .filter(|selector| self.values == Values::Unresolved || !is_variables_selector(selector))
+      .map(|selector| selector.to_string())
+      .collect();
+    if selectors.is_empty() {
+      return;
+    }
+    self.separate(indent);
+    self.out.push_str(&format!("{} {{\n", selectors.join(", ")));
+    for line in self.declarations(body) {
+      self.out.push_str(&format!("{}  {}\n", indent, line));
+    }
+    self.out.push_str(indent);
+    self.out.push_str("}\n");
+  }
+
+  // Comments on the line of a declaration stay there.
+  fn declarations(&self, body: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    // Only the first declaration of a property counts.
+    let mut seen = HashSet::new();
+    for segment in tokenizer::split(body, ';') {
+      let text = tokenizer::trim_comments(segment);
+      let start = if text.is_empty() { segment.len() } else { offset_in(segment, text) };
+      let (same_line, before) = segment[..start].split_once('\n').unwrap_or((&segment[..start], ""));
+      for comment in tokenizer::comments(same_line) {
+        match lines.last_mut() {
+          Some(line) => line.push_str(&format!(" {}", comment)),
+          None => lines.push(comment.into()),
+        }
+      }
+      lines.extend(tokenizer::comments(before).into_iter().map(String::from));
+      if text.is_empty() {
+        continue;
+      }
+      if let Some(line) = self.declaration(text, &mut seen) {
+        lines.push(line);
+      }
+      for comment in tokenizer::comments(&segment[start + text.len()..]) {
+        if let Some(line) = lines.last_mut() {
+          line.push_str(&format!(" {}", comment));
+        }
+      }
+    }
+    lines
+  }
+
+  fn declaration<'b>(&self, text: &'b str, seen: &mut HashSet<(&'b str, bool)>) -> Option<String> {
+    let declaration = tokenizer::declarations(text).into_iter().next()?.ok()?;
+    let important = if declaration.important { " !important" } else { "" };
+    let value = match self.values {
+      Values::Unresolved => normalize(declaration.value),
+      Values::Resolved => {
+        if !seen.insert((declaration.name, declaration.important)) {
+          return None;
+        }
+        let value = self.rules.variables().resolve(&tokenizer::strip_comments(declaration.value)).ok()?;
+        if value.trim() == "inherit" {
+          "inherit".into()
+        } else {
+          normalize(&functions::evaluate(&value).ok()?)
+        }
+      },
+    };
+    Some(format!("{}: {}{};", declaration.name, value, important))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::diagnostics::Severity;
+  use crate::media::{ColorScheme, Environment};
+  use crate::parser::parse_stylesheets;
+  use crate::properties::ComputedProperties;
+  use crate::testing::TestElement;
+
+  const DEFAULT_THEME: &str = include_str!("../../../theme.css");
+  const THEMES: [&str; 2] = [include_str!("../../../themes/compact.css"), include_str!("../../../themes/high-contrast.css")];
+
+  const EDGE_CASES: &str = r#"
+/* Leading comment */
+variables { accent: #f06; /* inline */ size: 12; }
+variables.dark { accent: #0f6; }
+
+@font-face {
+  font-family: "Fira Sans";
+  src: url("fonts/fira.otf"), url("/usr/share/fonts/fira.ttf");
+}
+
+label { color: var(accent) !important; font-size: var(size, 10) /* after */; }
+label.title, hbox > label:hover { color: var(missing, var(accent)); padding: var(size) /* inside */ 2; }
+/* Between blocks */
+vbox label#name { background: mix(var(accent), #000, 25%); border-color: lighten(var(missing, #333), 10%); }
+@media (max-width: 700px) { label { font-size: 10; } }
+@media (prefers-color-scheme: dark) { hbox { background: alpha(var(accent), 50%) !important; } }
+panel {
+  margin: 1
+    2;
+  border-width: 1; /* trailing */
+}
+"#;
+
+  // Everything the parser keeps: the rules, in order, and the font faces.
+  fn summary(rules: &Rules) -> Vec<String> {
+    let faces = rules.font_faces().iter().map(|face| format!("{:?}", face));
+    let rules = rules.rules().iter().map(|rule| {
+      format!(
+        "{} {:?} {:?} {:?} {:?}",
+        rule.selector(),
+        rule.specificity(),
+        rule.properties(),
+        rule.important_properties(),
+        rule.media
+      )
+    });
+    faces.chain(rules).collect()
+  }
+
+  fn environments() -> Vec<Environment> {
+    let dark = Environment {
+      color_scheme: ColorScheme::Dark,
+      ..Environment::default()
+    };
+    let narrow = Environment {
+      width: 600.0,
+      height: 400.0,
+      ..Environment::default()
+    };
+    vec![Environment::default(), dark, narrow]
+  }
+
+  fn round_trip(sources: &[&str], classes: &[&str]) {
+    let stylesheets = sources
+      .iter()
+      .enumerate()
+      .map(|(index, source)| Stylesheet::new(&format!("<{}>", index), source))
+      .collect();
+    let (mut rules, diagnostics) = parse_stylesheets(stylesheets, classes);
+    assert!(diagnostics.iter().all(|d| d.severity != Severity::Error), "{:?}", diagnostics);
+    for values in [Values::Unresolved, Values::Resolved] {
+      let serialized = serialize(&rules, values);
+      let (mut reparsed, reparsed_diagnostics) = parse_stylesheets(vec![Stylesheet::new("<serialized>", &serialized)], classes);
+      assert!(
+        reparsed_diagnostics.iter().all(|d| d.severity != Severity::Error),
+        "{:?}\n{}",
+        reparsed_diagnostics,
+        serialized
+      );
+      assert_eq!(summary(&rules), summary(&reparsed), "{:?}\n{}", values, serialized);
+      // Serializing is stable.
+      assert_eq!(serialize(&reparsed, values), serialized);
+
+      let elements: Vec<TestElement> = rules.rules().iter().map(|rule| TestElement::new(&rule.selector())).collect();
+      for environment in environments() {
+        rules.set_environment(environment.clone());
+        reparsed.set_environment(environment.clone());
+        for element in &elements {
+          let fields = |props: ComputedProperties| [props.fields(), props.spacing_fields()].concat();
+          assert_eq!(
+            fields(rules.solve(element)),
+            fields(reparsed.solve(element)),
+            "{:?} {:?} {}\n{}",
+            values,
+            environment,
+            element,
+            serialized
+          );
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn default_theme() {
+    for classes in [&[][..], &["light"], &["dark", "macos"]] {
+      round_trip(&[DEFAULT_THEME], classes);
+    }
+  }
+
+  #[test]
+  fn bundled_themes() {
+    for theme in THEMES {
+      for classes in [&["light"][..], &["dark"]] {
+        round_trip(&[DEFAULT_THEME, theme], classes);
+      }
+    }
+  }
+
+  #[test]
+  fn edge_cases() {
+    for classes in [&[][..], &["dark"]] {
+      round_trip(&[EDGE_CASES], classes);
+    }
+    let (rules, _) = parse_stylesheets(vec![Stylesheet::new("<edge>", EDGE_CASES)], &["dark"]);
+    let unresolved = serialize(&rules, Values::Unresolved);
+    assert!(unresolved.contains("/* Leading comment */"));
+    assert!(unresolved.contains("/* Between blocks */"));
+    assert!(unresolved.contains("color: var(accent) !important;"));
+    assert!(unresolved.contains("margin: 1 2;"));
+    let resolved = serialize(&rules, Values::Resolved);
+    assert!(!resolved.contains("var("));
+    assert!(!resolved.contains("variables"));
+    assert!(resolved.contains("color: #0f6 !important;"));
+    assert!(resolved.contains("background: rgba("));
+  }
+}
@@ -0,0 +1,116 @@
+//! Elements for the unit tests.
+
+use std::fmt;
+
+use crate::selector::{AttributeOperator, Element, PseudoClass};
+
+/// An element matching the simple selectors of `selector`, like
+/// `hbox > label#id.class:hover`. `+` makes the previous compound the previous
+/// sibling, the other combinators make it the parent. Other pseudo-classes and
+/// attributes are ignored.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct TestElement {
+  local: String,
+  id: Option<String>,
+  classes: Vec<String>,
+  pseudo_classes: Vec<PseudoClass>,
+  parent: Option<Box<TestElement>>,
+  prev_sibling: Option<Box<TestElement>>,
+}
+
+impl TestElement {
+  pub(crate) fn new(selector: &str) -> TestElement {
+    let selector = selector.replace('>', " ").replace('+', " + ");
+    let mut element: Option<TestElement> = None;
+    let mut sibling = false;
+    for compound in selector.split_whitespace() {
+      if compound == "+" {
+        sibling = true;
+        continue;
+      }
+      let mut next = compound_element(compound);
+      match element {
+        Some(prev) if sibling => {
+          next.parent = prev.parent.clone();
+          next.prev_sibling = Some(Box::new(prev));
+        },
+        prev => next.parent = prev.map(Box::new),
+      }
+      element = Some(next);
+      sibling = false;
+    }
+    element.unwrap_or_default()
+  }
+}
+
+fn compound_element(compound: &str) -> TestElement {
+  let mut element = TestElement::default();
+  let starts: Vec<usize> = compound.match_indices(['.', '#', ':', '[']).map(|(index, _)| index).collect();
+  let local_end = starts.first().copied().unwrap_or(compound.len());
+  element.local = compound[..local_end].to_string();
+  for (i, &start) in starts.iter().enumerate() {
+    let end = starts.get(i + 1).copied().unwrap_or(compound.len());
+    let name = &compound[start + 1..end];
+    match &compound[start..start + 1] {
+      "." => element.classes.push(name.to_string()),
+      "#" => element.id = Some(name.to_string()),
+      ":" => {
+        match name {
+          "hover" => element.pseudo_classes.push(PseudoClass::Hover),
+          "active" => element.pseudo_classes.push(PseudoClass::Active),
+          "focus" => element.pseudo_classes.push(PseudoClass::Focus),
+          _ => {},
+        }
+      },
+      _ => {},
+    }
+  }
+  element
+}
+
+impl fmt::Display for TestElement {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match (&self.prev_sibling, &self.parent) {
+      (Some(sibling), _) => write!(f, "{} + ", sibling)?,
+      (None, Some(parent)) => write!(f, "{} > ", parent)?,
+      (None, None) => {},
+    }
+    write!(f, "{}", self.local)?;
+    if let Some(id) = &self.id {
+      write!(f, "#{}", id)?;
+    }
+    for class in &self.classes {
+      write!(f, ".{}", class)?;
+    }
+    for class in &self.pseudo_classes {
+      write!(f, ":{}", class)?;
+    }
+    Ok(())
+  }
+}
+
+impl Element for TestElement {
+  fn parent_element(&self) -> Option<Self> {
+    self.parent.as_deref().cloned()
+  }
+
+  fn prev_sibling_element(&self) -> Option<Self> {
+    self.prev_sibling.as_deref().cloned()
+  }
+
+  fn has_local_name(&self, name: &str) -> bool {
+    self.local == name
+  }
+
+  fn attribute_matches(&self, attr: &str, operator: AttributeOperator<'_>) -> bool {
+    match (attr, operator) {
+      ("class", AttributeOperator::Contains(class)) => self.classes.iter().any(|c| c == class),
+      ("id", AttributeOperator::Matches(id)) => self.id.as_deref() == Some(id),
+      _ => false,
+    }
+  }
+
+  fn pseudo_class_matches(&self, class: PseudoClass) -> bool {
+    self.pseudo_classes.contains(&class)
+  }
+}
@@ -0,0 +1,254 @@
+//! Split a stylesheet into blocks and declarations.
+//! Every slice returned here borrows the source, so positions can be recovered
+//! for diagnostics. Comments, strings and parentheses are skipped over.
+
+use std::borrow::Cow;
+
+use simplecss::Declaration;
+
+pub struct Block<'a> {
+  /// Selectors or at-rule, without the surrounding comments.
+  pub prelude: &'a str,
+  /// Content between the braces. `None` for statements like `@import x;`.
+  pub body: Option<&'a str>,
+  /// False when the source ends before the closing `}`.
+  pub closed: bool,
+}
+
+pub fn blocks(source: &str) -> Vec<Block<'_>> {
+  let mut blocks = Vec::new();
+  let mut start = 0;
+  let mut scanner = Scanner::new(source);
+  while let Some((index, c)) = scanner.next_significant() {
+    match c {
+      ';' => {
+        blocks.push(Block {
+          prelude: trim_comments(&source[start..index]),
+          body: None,
+          closed: true,
+        });
+        start = index + 1;
+      },
+      '{' => {
+        let body_start = index + 1;
+        let closing = scanner.skip_block();
+        let body_end = closing.unwrap_or(source.len());
+        blocks.push(Block {
+          prelude: trim_comments(&source[start..index]),
+          body: Some(&source[body_start..body_end]),
+          closed: closing.is_some(),
+        });
+        start = (body_end + 1).min(source.len());
+      },
+      _ => {},
+    }
+  }
+  let rest = trim_comments(&source[start..]);
+  if !rest.is_empty() {
+    blocks.push(Block {
+      prelude: rest,
+      body: None,
+      closed: true,
+    });
+  }
+  blocks
+}
+
+/// Returns the declarations, or the offending text if it can't be parsed.
+pub fn declarations(body: &str) -> Vec<Result<Declaration<'_>, &str>> {
+  split(body, ';')
+    .into_iter()
+    .map(trim_comments)
+    .filter(|text| !text.is_empty())
+    .map(|text| {
+      let (name, value) = text.split_once(':').ok_or(text)?;
+      let name = trim_comments(name);
+      let mut value = trim_comments(value);
+      let mut important = false;
+      if let Some(index) = value.rfind('!') {
+        if value[index + 1..].trim() == "important" {
+          important = true;
+          value = trim_comments(&value[..index]);
+        }
+      }
+      let valid_name = !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+      if !valid_name || value.is_empty() {
+        return Err(text);
+      }
+      Ok(Declaration { name, value, important })
+    })
+    .collect()
+}
+
+/// Split at the top level separators, ignoring the ones in comments, strings
+/// or parentheses.
+pub fn split(text: &str, separator: char) -> Vec<&str> {
+  let mut parts = Vec::new();
+  let mut start = 0;
+  let mut scanner = Scanner::new(text);
+  while let Some((index, c)) = scanner.next_significant() {
+    match c {
+      '(' => scanner.depth += 1,
+      ')' => scanner.depth = scanner.depth.saturating_sub(1),
+      c if c == separator && scanner.depth == 0 => {
+        parts.push(&text[start..index]);
+        start = index + c.len_utf8();
+      },
+      _ => {},
+    }
+  }
+  parts.push(&text[start..]);
+  parts
+}
+
+/// Trim whitespace and comments on both ends.
+pub fn trim_comments(mut text: &str) -> &str {
+  loop {
+    let trimmed = text.trim();
+    let trimmed = match trimmed.strip_prefix("/*") {
+      Some(rest) => rest.find("*/").map_or("", |end| &rest[end + 2..]),
+      None => trimmed,
+    };
+    let trimmed = match trimmed.strip_suffix("*/") {
+      Some(rest) => rest.rfind("/*").map_or(rest, |start| &rest[..start]),
+      None => trimmed,
+    };
+    if trimmed.len() == text.len() {
+      return trimmed;
+    }
+    text = trimmed;
+  }
+}
+
+/// `text` with every comment outside of strings replaced by a space.
+pub fn strip_comments(text: &str) -> Cow<'_, str> {
+  if !text.contains("/*") {
+    return Cow::Borrowed(text);
+  }
+  let mut stripped = String::with_capacity(text.len());
+  let mut quote = None;
+  let mut escaped = false;
+  let mut rest = text;
+  while let Some(c) = rest.chars().next() {
+    if quote.is_none() && rest.starts_with("/*") {
+      let end = rest[2..].find("*/").map_or(rest.len(), |end| end + 4);
+      stripped.push(' ');
+      rest = &rest[end..];
+      continue;
+    }
+    match quote {
+      Some(q) if c == q && !escaped => quote = None,
+      Some(_) => {},
+      None if c == '"' || c == '\'' => quote = Some(c),
+      None => {},
+    }
+    escaped = c == '\\' && !escaped;
+    stripped.push(c);
+    rest = &rest[c.len_utf8()..];
+  }
+  Cow::Owned(stripped.trim().to_string())
+}
+
+/// Comments found in `text`, delimiters included.
+pub fn comments(text: &str) -> Vec<&str> {
+  let mut comments = Vec::new();
+  let mut rest = text;
+  while let Some(start) = rest.find("/*") {
+    let end = rest[start + 2..].find("*/").map_or(rest.len(), |end| start + 2 + end + 2);
+    comments.push(&rest[start..end]);
+    rest = &rest[end..];
+  }
+  comments
+}
+
+/// Byte offset of `slice` in `source`. `slice` must borrow `source`.
+pub fn offset_in(source: &str, slice: &str) -> usize {
+  (slice.as_ptr() as usize).saturating_sub(source.as_ptr() as usize).min(source.len())
+}
+
+struct Scanner<'a> {
+  text: &'a str,
+  chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+  depth: usize,
+}
+
+impl<'a> Scanner<'a> {
+  fn new(text: &'a str) -> Self {
+    Scanner {
+      text,
+      chars: text.char_indices().peekable(),
+      depth: 0,
+    }
+  }
+
+  /// Next character that is not part of a comment or a string.
+  fn next_significant(&mut self) -> Option<(usize, char)> {
+    while let Some((index, c)) = self.chars.next() {
+      match c {
+        '/' if self.text[index..].starts_with("/*") => {
+          self.chars.next();
+          self.skip_until("*/");
+        },
+        '"' | '\'' => {
+          let mut escaped = false;
+          for (_, s) in self.chars.by_ref() {
+            if s == c && !escaped {
+              break;
+            }
+            escaped = s == '\\' && !escaped;
+          }
+        },
+        _ => return Some((index, c)),
+      }
+    }
+    None
+  }
+
+  fn skip_until(&mut self, end: &str) {
+    while let Some((index, _)) = self.chars.next() {
+      if self.text[index..].starts_with(end) {
+        for _ in 1..end.len() {
+          self.chars.next();
+        }
+        return;
+      }
+    }
+  }
+
+  /// Skip a block whose `{` has already been consumed. Returns the position
+  /// of the closing `}`.
+  fn skip_block(&mut self) -> Option<usize> {
+    let mut depth = 0;
+    while let Some((index, c)) = self.next_significant() {
+      match c {
+        '{' => depth += 1,
+        '}' if depth == 0 => return Some(index),
+        '}' => depth -= 1,
+        _ => {},
+      }
+    }
+    None
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn strip_comments_in_values() {
+    assert_eq!(strip_comments("var(a) /* inline */ 2"), "var(a)   2");
+    assert_eq!(strip_comments("1/* x */2"), "1 2");
+    assert_eq!(strip_comments("url(\"a/*b*/c\") /* x"), "url(\"a/*b*/c\")");
+    assert!(matches!(strip_comments("1 2"), Cow::Borrowed("1 2")));
+  }
+
+  #[test]
+  fn unclosed_block() {
+    let blocks = blocks("a { color: red } b { color: red");
+    assert_eq!(blocks.len(), 2);
+    assert!(blocks[0].closed);
+    assert!(!blocks[1].closed);
+    assert_eq!(blocks[1].body, Some(" color: red"));
+  }
+}
@@ -0,0 +1,152 @@
+//! Variable substitution.
+//! Every `var(name)` or `var(name, fallback)` token found in a value is
+//! replaced by the value of the variable. Variables can be defined in terms of
+//! other variables.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Result};
+
+#[derive(Default)]
+pub struct Variables(HashMap<String, String>);
+
+impl Variables {
+  pub fn insert(&mut self, name: &str, value: &str) {
+    self.0.insert(name.into(), value.into());
+  }
+
+  pub fn resolve(&self, value: &str) -> Result<String> {
+    self.resolve_with_stack(value, &mut Vec::new())
+  }
+
+  // `stack` holds the variables being expanded, to detect cycles.
+  fn resolve_with_stack<'a>(&'a self, value: &str, stack: &mut Vec<&'a str>) -> Result<String> {
+    let mut resolved = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = find_var(rest) {
+      resolved.push_str(&rest[..start]);
+      let args_start = start + "var(".len();
+      let args_len = find_closing_parenthesis(&rest[args_start..]).ok_or_else(|| anyhow!("Missing `)` in `{}`", value))?;
+      let args = &rest[args_start..args_start + args_len];
+      let (name, fallback) = match split_fallback(args) {
+        Some((name, fallback)) => (name.trim(), Some(fallback.trim())),
+        None => (args.trim(), None),
+      };
+      match (self.0.get_key_value(name), fallback) {
+        (Some((name, var_value)), _) => {
+          if stack.contains(&name.as_str()) {
+            bail!("Cyclic variable: {} -> {}", stack.join(" -> "), name);
+          }
+          stack.push(name);
+          resolved.push_str(&self.resolve_with_stack(var_value, stack)?);
+          stack.pop();
+        },
+        (None, Some(fallback)) => resolved.push_str(&self.resolve_with_stack(fallback, stack)?),
+        (None, None) => bail!("Undefined variable: {}", name),
+      }
+      rest = &rest[args_start + args_len + 1..];
+    }
+    resolved.push_str(rest);
+    Ok(resolved)
+  }
+}
+
+/// Names of the variables used by `value`, fallbacks included.
+pub(crate) fn references(value: &str) -> Vec<&str> {
+  let mut names = Vec::new();
+  let mut rest = value;
+  while let Some(start) = find_var(rest) {
+    rest = &rest[start + "var(".len()..];
+    let args = &rest[..find_closing_parenthesis(rest).unwrap_or(rest.len())];
+    let name = split_fallback(args).map_or(args, |(name, _)| name);
+    names.push(name.trim());
+  }
+  names
+}
+
+// Position of the next `var(` that is not the tail of a longer identifier.
+fn find_var(value: &str) -> Option<usize> {
+  value.match_indices("var(").map(|(index, _)| index).find(|&index| {
+    value[..index]
+      .chars()
+      .next_back()
+      .map_or(true, |c| !(c.is_alphanumeric() || c == '-' || c == '_'))
+  })
+}
+
+// Index of the `)` closing an already opened parenthesis.
+pub(crate) fn find_closing_parenthesis(value: &str) -> Option<usize> {
+  let mut depth = 0;
+  for (index, c) in value.char_indices() {
+    match c {
+      '(' => depth += 1,
+      ')' if depth == 0 => return Some(index),
+      ')' => depth -= 1,
+      _ => {},
+    }
+  }
+  None
+}
+
+// Split `name, fallback` at the first top level comma.
+fn split_fallback(args: &str) -> Option<(&str, &str)> {
+  let mut depth = 0;
+  for (index, c) in args.char_indices() {
+    match c {
+      '(' => depth += 1,
+      ')' => depth -= 1,
+      ',' if depth == 0 => return Some((&args[..index], &args[index + 1..])),
+      _ => {},
+    }
+  }
+  None
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn variables(definitions: &[(&str, &str)]) -> Variables {
+    let mut variables = Variables::default();
+    for (name, value) in definitions {
+      variables.insert(name, value);
+    }
+    variables
+  }
+
+  #[test]
+  fn resolve() {
+    let variables = variables(&[("--a", "red"), ("--b", "var(--a)"), ("--c", "1px solid var(--b)")]);
+    assert_eq!(variables.resolve("var(--a)").unwrap(), "red");
+    assert_eq!(variables.resolve("var( --b )").unwrap(), "red");
+    assert_eq!(variables.resolve("var(--c)").unwrap(), "1px solid red");
+    assert_eq!(variables.resolve("mix(var(--a), var(--b), 0.5)").unwrap(), "mix(red, red, 0.5)");
+    assert_eq!(variables.resolve("no variable").unwrap(), "no variable");
+  }
+
+  #[test]
+  fn fallback() {
+    let variables = variables(&[("--a", "red")]);
+    assert_eq!(variables.resolve("var(--a, blue)").unwrap(), "red");
+    assert_eq!(variables.resolve("var(--x, blue)").unwrap(), "blue");
+    assert_eq!(variables.resolve("var(--x, var(--y, var(--a)))").unwrap(), "red");
+    assert_eq!(variables.resolve("var(--x, rgb(1, 2, 3))").unwrap(), "rgb(1, 2, 3)");
+    assert!(variables.resolve("var(--x, var(--y))").is_err());
+  }
+
+  #[test]
+  fn errors() {
+    let variables = variables(&[("--a", "var(--b)"), ("--b", "var(--a)"), ("--c", "var(--c)")]);
+    assert_eq!(variables.resolve("var(--x)").unwrap_err().to_string(), "Undefined variable: --x");
+    assert_eq!(variables.resolve("var(--a)").unwrap_err().to_string(), "Cyclic variable: --a -> --b -> --a");
+    assert_eq!(variables.resolve("var(--c)").unwrap_err().to_string(), "Cyclic variable: --c -> --c");
+    assert!(variables.resolve("var(--a").is_err());
+  }
+
+  #[test]
+  fn longer_identifier() {
+    let variables = variables(&[("--a", "red")]);
+    assert_eq!(variables.resolve("myvar(--a)").unwrap(), "myvar(--a)");
+    assert_eq!(references("myvar(--x) var(--a, var(--b))"), ["--a", "--b"]);
+  }
+}
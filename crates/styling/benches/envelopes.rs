@@ -0,0 +1,67 @@
+//! Cost of styling the envelope list for one frame.
+//! Run with `cargo bench -p styling`.
+
+#![feature(test)]
+
+extern crate test;
+
+use css::{parse_css_str, Rules};
+use styling::element::{self as elt, Element};
+use styling::{cache, BoxProperties};
+use test::Bencher;
+
+const THEME: &str = include_str!("../../../theme.css");
+
+// Roughly what fits on screen.
+const VISIBLE_ROWS: usize = 40;
+
+fn rules() -> Rules {
+  parse_css_str(THEME, &["macos", "dark"]).0
+}
+
+fn row_elt(index: usize) -> Element {
+  let mut row = elt::hbox().classes("envelope-row").position(index, VISIBLE_ROWS);
+  row.toggle_class("selected", index == 3);
+  row.toggle_class("unread", index % 3 == 0);
+  row.toggle_class("seen", index % 3 != 0);
+  row.toggle_class("flagged", index % 7 == 0);
+  row
+}
+
+fn row_elts(index: usize) -> Vec<Element> {
+  let mut row = row_elt(index);
+  if index > 0 {
+    row = row.prev_sibling(row_elt(index - 1));
+  }
+  ["flags-cell", "sender-cell", "subject-cell", "date-cell"]
+    .into_iter()
+    .enumerate()
+    .map(|(position, class)| elt::label().classes(class).position(position, 4).parent(row.clone()))
+    .chain(std::iter::once(row.clone()))
+    .collect()
+}
+
+fn frame(rules: &Rules) {
+  for index in 0..VISIBLE_ROWS {
+    for elt in row_elts(index) {
+      let props: BoxProperties = elt.compute(rules).into();
+      test::black_box(props);
+    }
+  }
+}
+
+#[bench]
+fn envelopes_frame_cached(b: &mut Bencher) {
+  let rules = rules();
+  frame(&rules);
+  b.iter(|| frame(&rules));
+}
+
+#[bench]
+fn envelopes_frame_uncached(b: &mut Bencher) {
+  let rules = rules();
+  b.iter(|| {
+    cache::clear();
+    frame(&rules)
+  });
+}
@@ -0,0 +1,195 @@
+//! Transitions between the computed styles of an element, as defined by its
+//! `transition` property. The animation state is kept in the egui memory, per
+//! element [Id] and property.
+//! Only colors and lengths in pixels are interpolated. Other values snap.
+
+use css::{Color as CSSColor, ComputedProperties as CSSProps, Length, Transition};
+use egui::{Context, Id};
+
+use crate::element::ComputedElement;
+
+#[derive(Clone, Copy)]
+enum Property {
+  Color(fn(&mut CSSProps) -> &mut CSSColor),
+  Length(fn(&mut CSSProps) -> &mut Length),
+}
+
+const PROPERTIES: [(&str, Property); 18] = [
+  ("color", Property::Color(|p| &mut p.color)),
+  ("background", Property::Color(|p| &mut p.background)),
+  ("border-color", Property::Color(|p| &mut p.border_color)),
+  ("stroke-color", Property::Color(|p| &mut p.stroke_color)),
+  ("underline-color", Property::Color(|p| &mut p.underline_color)),
+  ("strikethrough-color", Property::Color(|p| &mut p.strikethrough_color)),
+  ("font-size", Property::Length(|p| &mut p.font_size)),
+  ("border-width", Property::Length(|p| &mut p.border_width)),
+  ("stroke-width", Property::Length(|p| &mut p.stroke_width)),
+  ("underline-width", Property::Length(|p| &mut p.underline_width)),
+  ("strikethrough-width", Property::Length(|p| &mut p.strikethrough_width)),
+  ("height", Property::Length(|p| &mut p.height)),
+  ("width", Property::Length(|p| &mut p.width)),
+  ("min-width", Property::Length(|p| &mut p.min_width)),
+  ("min-height", Property::Length(|p| &mut p.min_height)),
+  ("max-width", Property::Length(|p| &mut p.max_width)),
+  ("max-height", Property::Length(|p| &mut p.max_height)),
+  ("expansion", Property::Length(|p| &mut p.expansion)),
+];
+
+// Colors as rgba, lengths as the first component.
+type Value = [f32; 4];
+
+fn get(property: Property, props: &mut CSSProps) -> Option<Value> {
+  match property {
+    Property::Color(field) => {
+      let color = field(props);
+      Some([color.r as f32, color.g as f32, color.b as f32, color.a])
+    },
+    Property::Length(field) => {
+      match *field(props) {
+        Length::Px(length) if length.is_finite() => Some([length, 0.0, 0.0, 0.0]),
+        _ => None,
+      }
+    },
+  }
+}
+
+fn set(property: Property, props: &mut CSSProps, value: Value) {
+  match property {
+    Property::Color(field) => {
+      let channel = |v: f32| v.round().clamp(0.0, 255.0) as u8;
+      *field(props) = CSSColor {
+        r: channel(value[0]),
+        g: channel(value[1]),
+        b: channel(value[2]),
+        a: value[3],
+      };
+    },
+    Property::Length(field) => *field(props) = Length::Px(value[0]),
+  }
+}
+
+#[derive(Clone, Copy)]
+struct State {
+  from: Value,
+  to: Value,
+  // In egui time.
+  start: f64,
+}
+
+impl State {
+  fn value(&self, now: f64, transition: &Transition) -> Value {
+    let progress = if transition.duration > 0.0 {
+      ((now - self.start) as f32 / transition.duration).clamp(0.0, 1.0)
+    } else {
+      1.0
+    };
+    let t = transition.easing.apply(progress);
+    let mut value = self.from;
+    for (v, to) in value.iter_mut().zip(self.to) {
+      *v += (to - *v) * t;
+    }
+    value
+  }
+}
+
+impl ComputedElement {
+  /// Transitions from the previous style of the element `id` to this one.
+  pub fn animate(mut self, ctx: &Context, id: Id) -> ComputedElement {
+    if self.props.transition.0.is_empty() {
+      return self;
+    }
+    let now = ctx.input().time;
+    let mut animating = false;
+    for (name, property) in PROPERTIES {
+      let transition = match self.props.transition.get(name) {
+        Some(transition) => transition.clone(),
+        None => continue,
+      };
+      let target = match get(property, &mut self.props) {
+        Some(target) => target,
+        None => continue,
+      };
+      let id = id.with(name);
+      let previous = ctx.data().get_temp::<State>(id);
+      let state = match previous {
+        Some(state) if state.to == target => state,
+        previous => {
+          let from = previous.map_or(target, |state| state.value(now, &transition));
+          let state = State { from, to: target, start: now };
+          ctx.data().insert_temp(id, state);
+          state
+        },
+      };
+      let value = state.value(now, &transition);
+      animating |= value != target;
+      set(property, &mut self.props, value);
+    }
+    if animating {
+      ctx.request_repaint();
+    }
+    self
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::element as elt;
+
+  // The properties of `element` in a frame at `time`, after setting the
+  // target values.
+  fn frame(ctx: &Context, time: f64, element: &ComputedElement, width: f32, height: f32) -> (Length, Length) {
+    ctx.begin_frame(egui::RawInput {
+      time: Some(time),
+      ..Default::default()
+    });
+    let mut element = element.clone();
+    element.props.max_width = Length::Px(width);
+    element.props.max_height = Length::Px(height);
+    let props = element.animate(ctx, Id::new("test")).props;
+    let _ = ctx.end_frame();
+    (props.max_width, props.max_height)
+  }
+
+  #[test]
+  fn interpolate() {
+    let (rules, _) = css::parse_css_str("label { transition: max-width 1s linear, max-height 2s linear; }", &[]);
+    let element = elt::label().compute(&rules);
+    let ctx = Context::default();
+    assert_eq!(frame(&ctx, 0.0, &element, 100.0, 100.0), (Length::Px(100.0), Length::Px(100.0)));
+    assert_eq!(frame(&ctx, 1.0, &element, 200.0, 300.0), (Length::Px(100.0), Length::Px(100.0)));
+    assert_eq!(frame(&ctx, 1.5, &element, 200.0, 300.0), (Length::Px(150.0), Length::Px(150.0)));
+    assert_eq!(frame(&ctx, 2.0, &element, 200.0, 300.0), (Length::Px(200.0), Length::Px(200.0)));
+    assert_eq!(frame(&ctx, 3.0, &element, 200.0, 300.0), (Length::Px(200.0), Length::Px(300.0)));
+    // Infinite lengths snap.
+    assert_eq!(frame(&ctx, 3.5, &element, f32::INFINITY, 300.0).0, Length::Px(f32::INFINITY));
+  }
+
+  #[test]
+  fn between_states() {
+    let (rules, _) = css::parse_css_str(
+      "label { background: #000; transition: background 1s linear; } label:hover { background: #c80; }",
+      &[],
+    );
+    let ctx = Context::default();
+    let id = Id::new("label");
+    let background = |time: f64, hover: bool| {
+      ctx.begin_frame(egui::RawInput {
+        time: Some(time),
+        ..Default::default()
+      });
+      let color = elt::label().hover(hover).compute(&rules).animate(&ctx, id).props.background;
+      let _ = ctx.end_frame();
+      (color.r, color.g, color.b)
+    };
+    assert_eq!(background(0.0, false), (0, 0, 0));
+    assert_eq!(background(1.0, true), (0, 0, 0));
+    assert_eq!(background(1.5, true), (102, 68, 0));
+    assert_eq!(background(2.0, true), (204, 136, 0));
+    // Back from the current value.
+    assert_eq!(background(3.0, false), (204, 136, 0));
+    assert_eq!(background(3.25, false), (153, 102, 0));
+    // Another id starts from its own state.
+    assert_eq!(elt::label().hover(true).compute(&rules).animate(&ctx, Id::new("other")).props.background.r, 204);
+  }
+}
@@ -0,0 +1,126 @@
+//! Computed properties, memoized by element signature. Solving an element
+//! walks all the rules, and the UI computes the same elements every frame.
+//! The cache is dropped when the rules change (on theme reload).
+//!
+//! Positions are reduced to the structural pseudo-classes they match (see
+//! [Element::cache_key]), and the cache keeps at most `2 * CAPACITY` entries:
+//! once `recent` is full it becomes `old`, and the entries of `old` that are
+//! used again move back to `recent`. The rest are dropped at the next swap.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use css::{ComputedProperties as CSSProps, PseudoClass, Rules};
+
+use crate::element::Element;
+
+const CAPACITY: usize = 2048;
+
+#[derive(Default)]
+struct Cache {
+  generation: u64,
+  // Structural pseudo-classes of the rules.
+  structural: Vec<PseudoClass>,
+  recent: HashMap<Element, CSSProps>,
+  old: HashMap<Element, CSSProps>,
+}
+
+impl Cache {
+  fn insert(&mut self, key: Element, props: CSSProps) {
+    if self.recent.len() >= CAPACITY {
+      self.old = std::mem::take(&mut self.recent);
+    }
+    self.recent.insert(key, props);
+  }
+
+  fn clear(&mut self) {
+    self.recent.clear();
+    self.old.clear();
+  }
+}
+
+thread_local! {
+  static CACHE: RefCell<Cache> = RefCell::new(Cache::default());
+}
+
+// The cache key of `element`, and its properties if they are cached.
+fn get(element: &Element, rules: &Rules) -> (Element, Option<CSSProps>) {
+  CACHE.with(|cache| {
+    let mut cache = cache.borrow_mut();
+    if cache.generation != rules.generation() {
+      cache.generation = rules.generation();
+      cache.structural = rules.structural_pseudo_classes();
+      cache.clear();
+    }
+    let key = element.cache_key(&cache.structural).into_owned();
+    if let Some(props) = cache.recent.get(&key) {
+      let props = props.clone();
+      return (key, Some(props));
+    }
+    match cache.old.remove(&key) {
+      Some(props) => {
+        cache.insert(key.clone(), props.clone());
+        (key, Some(props))
+      },
+      None => (key, None),
+    }
+  })
+}
+
+/// Parents are solved (and cached) first, to inherit their properties.
+pub(crate) fn solve(element: &Element, rules: &Rules) -> CSSProps {
+  let (key, props) = get(element, rules);
+  if let Some(props) = props {
+    return props;
+  }
+  let parent = element.parent_ref().map(|parent| solve(parent, rules));
+  let props = rules.solve_with_parent(element, parent.as_ref());
+  CACHE.with(|cache| cache.borrow_mut().insert(key, props.clone()));
+  props
+}
+
+/// Forget all the computed properties.
+pub fn clear() {
+  CACHE.with(|cache| cache.borrow_mut().clear());
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::element as elt;
+
+  fn len() -> usize {
+    CACHE.with(|cache| {
+      let cache = cache.borrow();
+      cache.recent.len() + cache.old.len()
+    })
+  }
+
+  #[test]
+  fn positions_share_entries() {
+    let (rules, _) = css::parse_css_str("label:nth-child(odd) { color: red; }", &[]);
+    clear();
+    for index in 0..10_000 {
+      let row = elt::hbox().position(index, 10_000);
+      elt::label().position(index % 7, 7).parent(row).compute(&rules);
+    }
+    // The rows by parity, and the labels by parity and parent.
+    assert_eq!(len(), 2 + 2 * 2);
+    assert_ne!(
+      elt::label().position(0, 7).compute(&rules).props().color,
+      elt::label().position(1, 7).compute(&rules).props().color
+    );
+  }
+
+  #[test]
+  fn bounded() {
+    let (rules, _) = css::parse_css_str("label { color: red; }", &[]);
+    clear();
+    for index in 0..10 * CAPACITY {
+      let id: &'static str = Box::leak(format!("label-{}", index).into_boxed_str());
+      elt::label().id(id).compute(&rules);
+      assert!(len() <= 2 * CAPACITY);
+    }
+    assert!(len() > CAPACITY);
+  }
+}
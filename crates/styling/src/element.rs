@@ -1,31 +1,65 @@
 //! Element are light object identifying a UI toolkit widget. For now, they are
 //! only used to be matched against the CSS stylesheet.
 
-use std::collections::HashSet;
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use std::sync::Arc;
 
 use css::{AttributeOperator, ComputedProperties as CSSProps, Element as CSSElement, PseudoClass, Rules};
+use egui::Vec2;
 
-#[derive(Debug, Clone)]
+use crate::cache;
+
+// Elements are hashed by their signature, parents included, to cache their
+// computed properties. See [crate::cache].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Element {
-  parent: Option<Box<Element>>,
+  parent: Option<Arc<Element>>,
   local: &'static str,
   id: Option<&'static str>,
-  classes: HashSet<&'static str>,
+  classes: BTreeSet<&'static str>,
   hover: bool,
   active: bool,
   focus: bool,
+  position: Option<Position>,
+  prev_sibling: Option<Arc<Element>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Position {
+  // Index and number of siblings, for the structural pseudo-classes.
+  Index(usize, usize),
+  // In cache keys only: the structural pseudo-classes of the rules that the
+  // index matches, one bit each. See [Element::cache_key].
+  Matches(u64),
 }
 
 #[derive(Clone)]
-pub struct ComputedElement(pub(crate) CSSProps);
+pub struct ComputedElement {
+  pub(crate) element: Element,
+  pub(crate) props: CSSProps,
+  pub(crate) root_font_size: f32,
+  // Percentages are relative to this size.
+  pub(crate) available_size: Vec2,
+}
 
 impl ComputedElement {
+  /// The element the properties were computed for.
+  pub fn element(&self) -> &Element {
+    &self.element
+  }
+
   pub fn props(&self) -> &CSSProps {
-    &self.0
+    &self.props
   }
 
   pub fn props_mut(&mut self) -> &mut CSSProps {
-    &mut self.0
+    &mut self.props
+  }
+
+  pub fn available_size(mut self, size: Vec2) -> ComputedElement {
+    self.available_size = size;
+    self
   }
 }
 
@@ -34,11 +68,13 @@ impl Element {
     Element {
       local,
       id: None,
-      classes: HashSet::new(),
+      classes: BTreeSet::new(),
       hover: false,
       active: false,
       focus: false,
       parent: None,
+      position: None,
+      prev_sibling: None,
     }
   }
 
@@ -68,7 +104,20 @@ impl Element {
   }
 
   pub fn parent(mut self, parent: Element) -> Element {
-    self.parent = Some(Box::new(parent));
+    self.parent = Some(Arc::new(parent));
+    self
+  }
+
+  /// Index (0-based) among `count` siblings.
+  pub fn position(mut self, index: usize, count: usize) -> Element {
+    self.position = Some(Position::Index(index, count));
+    self
+  }
+
+  /// Used by the `+` combinator. Selectors with several `+` need the sibling
+  /// of the sibling too.
+  pub fn prev_sibling(mut self, sibling: Element) -> Element {
+    self.prev_sibling = Some(Arc::new(sibling));
     self
   }
 
@@ -81,7 +130,7 @@ impl Element {
   }
 
   pub fn attach_parent(&mut self, parent: Element) {
-    self.parent = Some(Box::new(parent));
+    self.parent = Some(Arc::new(parent));
   }
 
   pub fn toggle_class(&mut self, class: &'static str, on: bool) {
@@ -92,9 +141,58 @@ impl Element {
     }
   }
 
+  pub(crate) fn parent_ref(&self) -> Option<&Element> {
+    self.parent.as_deref()
+  }
+
+  fn has_position(&self) -> bool {
+    self.position.is_some() ||
+      self.parent.as_ref().map_or(false, |parent| parent.has_position()) ||
+      self.prev_sibling.as_ref().map_or(false, |sibling| sibling.has_position())
+  }
+
+  /// `self` with the positions replaced by the `structural` pseudo-classes
+  /// they match, so that the siblings styled alike share a cache entry.
+  pub(crate) fn cache_key(&self, structural: &[PseudoClass]) -> Cow<'_, Element> {
+    if structural.len() > 64 || !self.has_position() {
+      return Cow::Borrowed(self);
+    }
+    let key = |elt: &Arc<Element>| {
+      match elt.cache_key(structural) {
+        Cow::Borrowed(_) => elt.clone(),
+        Cow::Owned(key) => Arc::new(key),
+      }
+    };
+    let position = self.position.map(|position| {
+      match position {
+        Position::Index(index, count) => {
+          let matches = structural
+            .iter()
+            .enumerate()
+            .filter(|(_, class)| class.matches_position(index, count))
+            .fold(0, |bits, (bit, _)| bits | 1 << bit);
+          Position::Matches(matches)
+        },
+        position => position,
+      }
+    });
+    Cow::Owned(Element {
+      parent: self.parent.as_ref().map(key),
+      prev_sibling: self.prev_sibling.as_ref().map(key),
+      position,
+      ..self.clone()
+    })
+  }
+
+  /// Cached until the rules change.
   pub fn compute(&self, rules: &Rules) -> ComputedElement {
-    let props = rules.solve(self);
-    ComputedElement(props)
+    let props = cache::solve(self, rules);
+    ComputedElement {
+      element: self.clone(),
+      props,
+      root_font_size: rules.root_font_size(),
+      available_size: Vec2::ZERO,
+    }
   }
 }
 
@@ -119,6 +217,9 @@ impl std::fmt::Display for Element {
     if self.focus {
       write!(f, ":focus")?;
     }
+    if let Some(Position::Index(index, _)) = self.position {
+      write!(f, ":nth-child({})", index + 1)?;
+    }
 
     Ok(())
   }
@@ -126,11 +227,11 @@ impl std::fmt::Display for Element {
 
 impl CSSElement for Element {
   fn parent_element(&self) -> Option<Self> {
-    self.parent.as_ref().map(|elt| *elt.clone())
+    self.parent.as_deref().cloned()
   }
 
   fn prev_sibling_element(&self) -> Option<Self> {
-    None
+    self.prev_sibling.as_deref().cloned()
   }
 
   fn has_local_name(&self, name: &str) -> bool {
@@ -145,16 +246,24 @@ impl CSSElement for Element {
     }
   }
 
-  fn pseudo_class_matches(&self, class: PseudoClass<'_>) -> bool {
+  fn pseudo_class_matches(&self, class: PseudoClass) -> bool {
     match class {
       PseudoClass::Hover => self.hover,
       PseudoClass::Active => self.active,
       PseudoClass::Focus => self.focus,
-      _ => false,
+      _ => {
+        match self.position {
+          Some(Position::Index(index, count)) => class.matches_position(index, count),
+          _ => false,
+        }
+      },
     }
   }
 }
 
+/// Local names of the elements built by the functions below.
+pub const LOCAL_NAMES: [&str; 7] = ["window", "native", "label", "vbox", "hbox", "panel", "spacing"];
+
 pub fn window() -> Element {
   Element::new("window")
 }
@@ -178,3 +287,8 @@ pub fn hbox() -> Element {
 pub fn panel() -> Element {
   Element::new("panel")
 }
+
+/// Sizes of the native widgets. See [crate::NativeSizes].
+pub fn spacing() -> Element {
+  Element::new("spacing")
+}
@@ -1,6 +1,39 @@
 use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
 
+use css::{FontFamily as CSSFontFamily, Rules};
 use egui::{Context, FontData, FontDefinitions, FontFamily, FontId, TextStyle};
+use log::{error, info};
+
+// `@font-face` families egui knows about. Using a family before egui loaded it
+// panics, so new families stay pending until then. See [sync]. The lock is
+// never held while calling egui, which has its own locks.
+static FAMILIES: Mutex<Families> = Mutex::new(Families {
+  loaded: Vec::new(),
+  pending: Vec::new(),
+});
+
+struct Families {
+  loaded: Vec<String>,
+  pending: Vec<String>,
+}
+
+// Font files already read. Fonts are large and the rules are applied again on
+// every reload or appearance change. Only the files used by the last
+// [register] are kept, and a file is read again when it changes.
+static FILES: Mutex<Vec<FontFile>> = Mutex::new(Vec::new());
+
+#[derive(Clone)]
+struct FontFile {
+  path: PathBuf,
+  modified: Option<SystemTime>,
+  data: FontData,
+}
+
+// `@font-face` family used by all the other families.
+const FALLBACK: &str = "fallback";
 
 pub fn regular() -> FontFamily {
   FontFamily::Name("Regular".into())
@@ -13,6 +46,18 @@ pub fn bold() -> FontFamily {
   FontFamily::Name("Bold".into())
 }
 
+/// `@font-face` families that are not loaded yet fall back to the regular
+/// font.
+pub fn family(family: &CSSFontFamily) -> FontFamily {
+  match family {
+    CSSFontFamily::Regular => regular(),
+    CSSFontFamily::Bold => bold(),
+    CSSFontFamily::Mono => mono(),
+    CSSFontFamily::Named(name) if FAMILIES.lock().unwrap().loaded.contains(name) => FontFamily::Name(name.as_str().into()),
+    CSSFontFamily::Named(_) => regular(),
+  }
+}
+
 pub fn text_styles_for_size(size: f32) -> BTreeMap<TextStyle, FontId> {
   let mut text_styles = BTreeMap::new();
 
@@ -25,7 +70,59 @@ pub fn text_styles_for_size(size: f32) -> BTreeMap<TextStyle, FontId> {
   text_styles
 }
 
-pub fn register(ctx: &Context) {
+// A font file, or all the font files of a directory, sorted by name. The files
+// read are added to `files`.
+fn load(path: &Path, cache: &[FontFile], files: &mut Vec<FontFile>) -> std::io::Result<Vec<(String, FontData)>> {
+  let mut paths = if path.is_dir() {
+    std::fs::read_dir(path)?
+      .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+      .filter(|path| {
+        let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or_default().to_lowercase();
+        matches!(extension.as_str(), "ttf" | "otf" | "ttc")
+      })
+      .collect()
+  } else {
+    vec![path.to_path_buf()]
+  };
+  paths.sort();
+  paths
+    .into_iter()
+    .map(|path| Ok((path.display().to_string(), read(path, cache, files)?)))
+    .collect()
+}
+
+// From `cache` or `files` if the file didn't change since.
+fn read(path: PathBuf, cache: &[FontFile], files: &mut Vec<FontFile>) -> std::io::Result<FontData> {
+  let modified = std::fs::metadata(&path)?.modified().ok();
+  let cached = |file: &&FontFile| file.path == path && file.modified.is_some() && file.modified == modified;
+  if let Some(file) = files.iter().find(cached) {
+    return Ok(file.data.clone());
+  }
+  let file = match cache.iter().find(cached) {
+    Some(file) => file.clone(),
+    None => {
+      FontFile {
+        data: FontData::from_owned(std::fs::read(&path)?),
+        path,
+        modified,
+      }
+    },
+  };
+  files.push(file.clone());
+  Ok(file.data)
+}
+
+/// Registers the bundled fonts and the `@font-face` fonts of `rules`. Call
+/// again when the rules change.
+///
+/// Each family falls back to the `fallback` faces, then to the egui default
+/// fonts (emojis), for the glyphs it lacks. egui only has default fonts with
+/// its `default_fonts` feature, which this crate leaves to the application
+/// (eframe enables it by default).
+///
+/// The font files are not watched: a changed file is only read again on the
+/// next call.
+pub fn register(ctx: &Context, rules: &Rules) {
   // Register fonts
 
   let font_data_regular = FontData::from_static(include_bytes!("../../../inconsolata-nerd-font/regular.ttf"));
@@ -38,15 +135,96 @@ pub fn register(ctx: &Context) {
 
   let mut fonts = FontDefinitions::empty();
 
-  fonts.families.insert(regular(), vec![font_name_regular.clone()]);
-  fonts.families.insert(mono(), vec![font_name_mono.clone()]);
-  fonts.families.insert(bold(), vec![font_name_bold.clone()]);
-  fonts.families.insert(FontFamily::Proportional, vec![font_name_regular.clone()]);
-  fonts.families.insert(FontFamily::Monospace, vec![font_name_mono.clone()]);
+  // Several faces can share a family. Keyed by family.
+  let mut faces: BTreeMap<String, Vec<String>> = BTreeMap::new();
+  let cache = std::mem::take(&mut *FILES.lock().unwrap());
+  let mut files = Vec::new();
+  for face in rules.font_faces() {
+    let family = match face.family.to_lowercase().as_str() {
+      keyword @ ("regular" | "bold" | "mono" | FALLBACK) => keyword.to_owned(),
+      _ => face.family.clone(),
+    };
+    let loaded = face
+      .sources
+      .iter()
+      .find_map(|path| load(path, &cache, &mut files).ok().filter(|fonts| !fonts.is_empty()));
+    match loaded {
+      Some(loaded) => {
+        for (font_name, data) in loaded {
+          let font_names = faces.entry(family.clone()).or_default();
+          // The default and the user themes can declare the same faces.
+          if !font_names.contains(&font_name) {
+            fonts.font_data.insert(font_name.clone(), data);
+            font_names.push(font_name);
+          }
+        }
+      },
+      None if family == FALLBACK => info!("Skipping the fallback font-face. No readable source in {:?}", face.sources),
+      None => error!("Can't load the font-face {}. No readable source in {:?}", face.family, face.sources),
+    }
+  }
+  *FILES.lock().unwrap() = files;
+
+  let defaults = FontDefinitions::default();
+  let default_names = defaults.families.get(&FontFamily::Proportional).cloned().unwrap_or_default();
+  let mut fallbacks = faces.remove(FALLBACK).unwrap_or_default();
+  fallbacks.extend(default_names.iter().cloned());
+  for name in default_names {
+    if let Some(data) = defaults.font_data.get(&name) {
+      fonts.font_data.insert(name, data.clone());
+    }
+  }
+
+  let mut chain = |keyword: &str, bundled: &str| -> Vec<String> {
+    let mut chain = faces.remove(keyword).unwrap_or_default();
+    chain.push(bundled.to_owned());
+    chain.extend(fallbacks.iter().cloned());
+    chain
+  };
+  let regular_chain = chain("regular", &font_name_regular);
+  let mono_chain = chain("mono", &font_name_mono);
+  let bold_chain = chain("bold", &font_name_bold);
+
+  fonts.families.insert(regular(), regular_chain.clone());
+  fonts.families.insert(mono(), mono_chain.clone());
+  fonts.families.insert(bold(), bold_chain);
+  fonts.families.insert(FontFamily::Proportional, regular_chain);
+  fonts.families.insert(FontFamily::Monospace, mono_chain);
+
+  // The remaining faces are named families.
+  for (name, font_names) in &faces {
+    let chain = font_names.iter().chain([&font_name_regular]).chain(&fallbacks).cloned().collect();
+    fonts.families.insert(FontFamily::Name(name.as_str().into()), chain);
+  }
 
   fonts.font_data.insert(font_name_regular, font_data_regular);
   fonts.font_data.insert(font_name_mono, font_data_mono);
   fonts.font_data.insert(font_name_bold, font_data_bold);
 
+  // Removed families are dropped right away, new ones once loaded.
+  {
+    let mut families = FAMILIES.lock().unwrap();
+    families.loaded.retain(|name| faces.contains_key(name));
+    families.pending = faces.into_keys().filter(|name| !families.loaded.contains(name)).collect();
+  }
+
   ctx.set_fonts(fonts);
 }
+
+/// Makes the fonts registered with [register] usable once egui loaded them.
+/// Call at the start of every frame.
+pub fn sync(ctx: &Context) {
+  let pending = FAMILIES.lock().unwrap().pending.clone();
+  if pending.is_empty() {
+    return;
+  }
+  let available = ctx.fonts().families();
+  if pending.iter().all(|name| available.contains(&FontFamily::Name(name.as_str().into()))) {
+    let mut families = FAMILIES.lock().unwrap();
+    // Unless registered again meanwhile.
+    if families.pending == pending {
+      families.pending.clear();
+      families.loaded.extend(pending);
+    }
+  }
+}
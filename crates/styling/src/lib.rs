@@ -1,5 +1,7 @@
+mod animation;
+pub mod cache;
 pub mod element;
 pub mod fonts;
 mod properties;
 
-pub use properties::{BoxProperties, TextColors};
+pub use properties::{BoxProperties, LayoutProperties, NativeSizes, TextColors, TextLayout};
@@ -1,9 +1,9 @@
 //! Subsets of CSS properties.
 //! Translates CSS properties in stuctures understood by the UI toolkit.
 
-use css::{Align as CSSAlign, Color as CSSColor, FontFamily as CSSFontFamily, Radius, Sides};
-use egui::style::{Margin, Selection, WidgetVisuals};
-use egui::{epaint, Align, Align2, Color32, FontId, Frame, Rounding, Stroke, TextFormat};
+use css::{Align as CSSAlign, BoxShadow, Color as CSSColor, Direction, Display, Justify, Length, Radius, Sides, Size, TextOverflow, WhiteSpace};
+use egui::style::{Interaction, Margin, Selection, Spacing, WidgetVisuals};
+use egui::{epaint, vec2, Align, Align2, Color32, FontId, Frame, Rounding, Stroke, TextFormat, Vec2};
 
 use crate::element::ComputedElement;
 use crate::fonts;
@@ -13,6 +13,30 @@ pub struct TextColors {
   pub bg: Color32,
 }
 
+/// Native sizes that egui keeps in its visuals.
+pub struct NativeSizes {
+  pub resize_corner_size: f32,
+  pub text_cursor_width: f32,
+  pub clip_rect_margin: f32,
+}
+
+pub struct TextLayout {
+  pub wrap: bool,
+  pub ellipsis: bool,
+  /// 0 to use the height of the font.
+  pub line_height: f32,
+}
+
+/// How a container lays out its children, and how it grows in its parent.
+pub struct LayoutProperties {
+  pub visible: bool,
+  pub horizontal: bool,
+  /// Along the main axis.
+  pub gap: f32,
+  pub justify: Justify,
+  pub flex_grow: f32,
+}
+
 pub struct BoxProperties {
   pub align: Align2,
   pub padding: Margin,
@@ -39,43 +63,65 @@ fn to_align(align: &CSSAlign) -> Align {
   }
 }
 
-fn to_rounding(radius: &Radius) -> Rounding {
-  Rounding {
-    nw: radius.nw,
-    ne: radius.ne,
-    sw: radius.sw,
-    se: radius.se,
+impl ComputedElement {
+  fn font_size(&self) -> f32 {
+    let root = self.root_font_size;
+    self.props.font_size.resolve(root, root, root)
+  }
+
+  fn horizontal(&self, length: &Length) -> f32 {
+    length.resolve(self.font_size(), self.root_font_size, self.available_size.x)
   }
-}
 
-fn to_margin(sides: &Sides) -> Margin {
-  Margin {
-    top: sides.top,
-    left: sides.left,
-    bottom: sides.bottom,
-    right: sides.right,
+  fn vertical(&self, length: &Length) -> f32 {
+    length.resolve(self.font_size(), self.root_font_size, self.available_size.y)
+  }
+
+  fn rounding(&self, radius: &Radius) -> Rounding {
+    Rounding {
+      nw: self.horizontal(&radius.nw),
+      ne: self.horizontal(&radius.ne),
+      sw: self.horizontal(&radius.sw),
+      se: self.horizontal(&radius.se),
+    }
+  }
+
+  // egui shadows are centered: the parser rejects offsets.
+  fn shadow(&self, shadow: &BoxShadow) -> epaint::Shadow {
+    epaint::Shadow {
+      extrusion: self.horizontal(&shadow.blur),
+      color: to_color(&shadow.color),
+    }
+  }
+
+  fn size(&self, size: &Size) -> Vec2 {
+    vec2(self.horizontal(&size.x), self.vertical(&size.y))
+  }
+
+  fn margin(&self, sides: &Sides) -> Margin {
+    Margin {
+      top: self.vertical(&sides.top),
+      left: self.horizontal(&sides.left),
+      bottom: self.vertical(&sides.bottom),
+      right: self.horizontal(&sides.right),
+    }
   }
 }
 
 impl From<ComputedElement> for WidgetVisuals {
   fn from(e: ComputedElement) -> WidgetVisuals {
     WidgetVisuals {
-      bg_fill: to_color(&e.0.background),
+      bg_fill: to_color(&e.props.background),
       bg_stroke: Stroke {
-        color: to_color(&e.0.border_color),
-        width: e.0.border_width,
+        color: to_color(&e.props.border_color),
+        width: e.horizontal(&e.props.border_width),
       },
       fg_stroke: Stroke {
-        color: to_color(&e.0.stroke_color),
-        width: e.0.stroke_width,
-      },
-      rounding: Rounding {
-        nw: e.0.radius.nw,
-        ne: e.0.radius.ne,
-        sw: e.0.radius.sw,
-        se: e.0.radius.se,
+        color: to_color(&e.props.stroke_color),
+        width: e.horizontal(&e.props.stroke_width),
       },
-      expansion: e.0.expansion,
+      rounding: e.rounding(&e.props.radius),
+      expansion: e.horizontal(&e.props.expansion),
     }
   }
 }
@@ -83,10 +129,10 @@ impl From<ComputedElement> for WidgetVisuals {
 impl From<ComputedElement> for Selection {
   fn from(e: ComputedElement) -> Selection {
     Selection {
-      bg_fill: to_color(&e.0.background),
+      bg_fill: to_color(&e.props.background),
       stroke: Stroke {
-        color: to_color(&e.0.stroke_color),
-        width: e.0.stroke_width,
+        color: to_color(&e.props.stroke_color),
+        width: e.horizontal(&e.props.stroke_width),
       },
     }
   }
@@ -95,46 +141,62 @@ impl From<ComputedElement> for Selection {
 impl From<ComputedElement> for TextFormat {
   fn from(e: ComputedElement) -> TextFormat {
     TextFormat {
-      font_id: {
-        let family = match e.0.font_family {
-          CSSFontFamily::Regular => fonts::regular(),
-          CSSFontFamily::Bold => fonts::bold(),
-          CSSFontFamily::Mono => fonts::mono(),
-        };
-        FontId::new(e.0.font_size, family)
-      },
-      color: to_color(&e.0.color),
-      background: to_color(&e.0.background),
-      italics: e.0.italics,
-      valign: to_align(&e.0.align),
+      font_id: FontId::new(e.font_size(), fonts::family(&e.props.font_family)),
+      color: to_color(&e.props.color),
+      background: to_color(&e.props.background),
+      italics: e.props.italics,
+      valign: to_align(&e.props.align),
       strikethrough: Stroke {
-        width: e.0.strikethrough_width,
-        color: to_color(&e.0.strikethrough_color),
+        width: e.vertical(&e.props.strikethrough_width),
+        color: to_color(&e.props.strikethrough_color),
       },
       underline: Stroke {
-        width: e.0.underline_width,
-        color: to_color(&e.0.underline_color),
+        width: e.vertical(&e.props.underline_width),
+        color: to_color(&e.props.underline_color),
       },
     }
   }
 }
 
+impl From<ComputedElement> for TextLayout {
+  fn from(e: ComputedElement) -> TextLayout {
+    TextLayout {
+      wrap: e.props.white_space == WhiteSpace::Wrap,
+      ellipsis: e.props.text_overflow == TextOverflow::Ellipsis,
+      line_height: e.vertical(&e.props.line_height),
+    }
+  }
+}
+
+impl From<ComputedElement> for LayoutProperties {
+  fn from(e: ComputedElement) -> LayoutProperties {
+    let horizontal = e.props.direction == Direction::Row;
+    LayoutProperties {
+      visible: e.props.display != Display::None,
+      horizontal,
+      gap: if horizontal { e.horizontal(&e.props.gap) } else { e.vertical(&e.props.gap) },
+      justify: e.props.justify_content,
+      flex_grow: e.props.flex_grow.max(0.0),
+    }
+  }
+}
+
 impl From<ComputedElement> for BoxProperties {
   fn from(e: ComputedElement) -> BoxProperties {
     BoxProperties {
-      align: Align2([to_align(&e.0.align), to_align(&e.0.cross_align)]),
-      padding: to_margin(&e.0.padding),
-      rounding: to_rounding(&e.0.radius),
-      min_width: e.0.min_width,
-      max_width: e.0.max_width,
-      min_height: e.0.min_height,
-      max_height: e.0.max_height,
-      background: to_color(&e.0.background),
-      width: e.0.width,
-      height: e.0.height,
+      align: Align2([to_align(&e.props.align), to_align(&e.props.cross_align)]),
+      padding: e.margin(&e.props.padding),
+      rounding: e.rounding(&e.props.radius),
+      min_width: e.horizontal(&e.props.min_width),
+      max_width: e.horizontal(&e.props.max_width),
+      min_height: e.vertical(&e.props.min_height),
+      max_height: e.vertical(&e.props.max_height),
+      background: to_color(&e.props.background),
+      width: e.horizontal(&e.props.width),
+      height: e.vertical(&e.props.height),
       border: Stroke {
-        color: to_color(&e.0.border_color),
-        width: e.0.border_width,
+        color: to_color(&e.props.border_color),
+        width: e.horizontal(&e.props.border_width),
       },
     }
   }
@@ -143,14 +205,14 @@ impl From<ComputedElement> for BoxProperties {
 impl From<ComputedElement> for Frame {
   fn from(e: ComputedElement) -> Frame {
     Frame {
-      inner_margin: to_margin(&e.0.padding),
-      outer_margin: to_margin(&e.0.margin),
-      rounding: to_rounding(&e.0.radius),
-      shadow: epaint::Shadow::default(),
-      fill: to_color(&e.0.background),
+      inner_margin: e.margin(&e.props.padding),
+      outer_margin: e.margin(&e.props.margin),
+      rounding: e.rounding(&e.props.radius),
+      shadow: e.shadow(&e.props.box_shadow),
+      fill: to_color(&e.props.background),
       stroke: Stroke {
-        color: to_color(&e.0.border_color),
-        width: e.0.border_width,
+        color: to_color(&e.props.border_color),
+        width: e.horizontal(&e.props.border_width),
       },
     }
   }
@@ -159,8 +221,74 @@ impl From<ComputedElement> for Frame {
 impl From<ComputedElement> for TextColors {
   fn from(e: ComputedElement) -> TextColors {
     TextColors {
-      fg: to_color(&e.0.color),
-      bg: to_color(&e.0.background),
+      fg: to_color(&e.props.color),
+      bg: to_color(&e.props.background),
+    }
+  }
+}
+
+impl From<ComputedElement> for Spacing {
+  fn from(e: ComputedElement) -> Spacing {
+    Spacing {
+      item_spacing: e.size(&e.props.item_spacing),
+      window_margin: e.margin(&e.props.window_margin),
+      button_padding: e.size(&e.props.button_padding),
+      indent: e.horizontal(&e.props.indent),
+      interact_size: e.size(&e.props.interact_size),
+      slider_width: e.horizontal(&e.props.slider_width),
+      text_edit_width: e.horizontal(&e.props.text_edit_width),
+      icon_width: e.horizontal(&e.props.icon_width),
+      icon_width_inner: e.horizontal(&e.props.icon_width_inner),
+      icon_spacing: e.horizontal(&e.props.icon_spacing),
+      tooltip_width: e.horizontal(&e.props.tooltip_width),
+      combo_height: e.vertical(&e.props.combo_height),
+      scroll_bar_width: e.horizontal(&e.props.scroll_bar_width),
+      indent_ends_with_horizontal_line: e.props.indent_ends_with_horizontal_line,
+      menu_margin: e.margin(&e.props.menu_margin),
     }
   }
 }
+
+impl From<ComputedElement> for Interaction {
+  fn from(e: ComputedElement) -> Interaction {
+    Interaction {
+      resize_grab_radius_side: e.horizontal(&e.props.resize_grab_radius_side),
+      resize_grab_radius_corner: e.horizontal(&e.props.resize_grab_radius_corner),
+      show_tooltips_only_when_still: e.props.show_tooltips_only_when_still,
+    }
+  }
+}
+
+impl From<ComputedElement> for NativeSizes {
+  fn from(e: ComputedElement) -> NativeSizes {
+    NativeSizes {
+      resize_corner_size: e.horizontal(&e.props.resize_corner_size),
+      text_cursor_width: e.horizontal(&e.props.text_cursor_width),
+      clip_rect_margin: e.horizontal(&e.props.clip_rect_margin),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::element as elt;
+
+  #[test]
+  fn spacing() {
+    let source = "spacing { item-spacing: 10 4; scroll-bar-width: 1em; font-size: 12; resize-grab-radius-side: 7; show-tooltips-only-when-still: false; }";
+    let (rules, diagnostics) = css::parse_css_str(source, &[]);
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    let spacing: Spacing = elt::spacing().compute(&rules).into();
+    assert_eq!(spacing.item_spacing, vec2(10.0, 4.0));
+    assert_eq!(spacing.scroll_bar_width, 12.0);
+    // Unset sizes keep the defaults.
+    assert_eq!(spacing.icon_width, 14.0);
+    let interaction: Interaction = elt::spacing().compute(&rules).into();
+    assert_eq!(interaction.resize_grab_radius_side, 7.0);
+    assert!(!interaction.show_tooltips_only_when_still);
+    // Only on the `spacing` element.
+    let label: Spacing = elt::label().compute(&rules).into();
+    assert_eq!(label.item_spacing, vec2(8.0, 3.0));
+  }
+}
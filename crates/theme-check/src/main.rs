@@ -0,0 +1,93 @@
+//! Checks themes without starting the UI: parsing errors, unknown properties,
+//! invalid values, undefined and unused variables, and selectors that can't
+//! match any element.
+//!
+//! ```text
+//! himalaya-gui-theme-check [--deny-warnings] [--classes macos,dark] theme.css [more.css...]
+//! ```
+//!
+//! Several stylesheets are layered, like the default and the user themes.
+//! Variables are resolved for every OS and color scheme, unless `--classes`
+//! is given. Exits with 1 if there are errors, or warnings with
+//! `--deny-warnings`.
+
+use std::collections::HashSet;
+use std::process::ExitCode;
+
+use anyhow::{anyhow, bail, Result};
+use css::{lint, parse_stylesheets, Severity, Stylesheet};
+use styling::element::LOCAL_NAMES;
+
+const OSES: [&str; 3] = ["macos", "linux", "windows"];
+const COLOR_SCHEMES: [&str; 2] = ["light", "dark"];
+
+struct Options {
+  paths: Vec<String>,
+  deny_warnings: bool,
+  classes: Option<Vec<String>>,
+}
+
+fn parse_args() -> Result<Options> {
+  let mut options = Options {
+    paths: Vec::new(),
+    deny_warnings: false,
+    classes: None,
+  };
+  let mut args = std::env::args().skip(1);
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--deny-warnings" => options.deny_warnings = true,
+      "--classes" => {
+        let classes = args.next().ok_or_else(|| anyhow!("--classes needs a value"))?;
+        options.classes = Some(classes.split(',').map(|class| class.trim().to_owned()).collect());
+      },
+      flag if flag.starts_with("--") => bail!("Unknown option: {}", flag),
+      path => options.paths.push(path.to_owned()),
+    }
+  }
+  if options.paths.is_empty() {
+    bail!("Usage: himalaya-gui-theme-check [--deny-warnings] [--classes macos,dark] theme.css [more.css...]");
+  }
+  Ok(options)
+}
+
+// Returns whether the themes pass.
+fn check(options: &Options) -> Result<bool> {
+  let class_sets: Vec<Vec<&str>> = match &options.classes {
+    Some(classes) => vec![classes.iter().map(String::as_str).collect()],
+    None => OSES.iter().flat_map(|os| COLOR_SCHEMES.iter().map(move |scheme| vec![*os, *scheme])).collect(),
+  };
+
+  // The same problem is usually found for every set of classes.
+  let mut reported = HashSet::new();
+  let (mut errors, mut warnings) = (0, 0);
+  let stylesheets = options.paths.iter().map(Stylesheet::from_path).collect::<Result<Vec<_>>>()?;
+  for classes in class_sets {
+    let (rules, mut diagnostics) = parse_stylesheets(stylesheets.clone(), &classes);
+    diagnostics.extend(lint(&rules, &LOCAL_NAMES));
+    for diagnostic in diagnostics {
+      if !reported.insert(diagnostic.to_string()) {
+        continue;
+      }
+      println!("{}", diagnostic);
+      match diagnostic.severity {
+        Severity::Error => errors += 1,
+        Severity::Warning => warnings += 1,
+      }
+    }
+  }
+
+  eprintln!("{} error(s), {} warning(s)", errors, warnings);
+  Ok(errors == 0 && !(options.deny_warnings && warnings > 0))
+}
+
+fn main() -> ExitCode {
+  match parse_args().and_then(|options| check(&options)) {
+    Ok(true) => ExitCode::SUCCESS,
+    Ok(false) => ExitCode::FAILURE,
+    Err(e) => {
+      eprintln!("Error: {}", e);
+      ExitCode::from(2)
+    },
+  }
+}
@@ -6,6 +6,7 @@ use egui::{vec2, CentralPanel, Context, Frame, Key, Modifiers, Rgba, ScrollArea,
 use log::{error, warn};
 use styling::{element as elt, BoxProperties};
 
+use crate::options::Options;
 use crate::server::{self, AccountId, Envelope, MailId, MboxId, Mboxes, Server, ServerCmd, ServerEvent};
 use crate::theme::Theme;
 use crate::ui;
@@ -26,13 +27,13 @@ pub struct App {
 }
 
 impl App {
-  pub fn new(cc: &eframe::CreationContext<'_>) -> Result<Self> {
+  pub fn new(cc: &eframe::CreationContext<'_>, options: &Options) -> Result<Self> {
     let ctx = &cc.egui_ctx;
 
-    let theme = Theme::init(cc)?;
-
     let accounts = server::accounts().expect("Account listing failed");
 
+    let theme = Theme::init(cc, options)?;
+
     let servers: HashMap<AccountId, Server> = accounts
       .iter()
       .map(|account_name| {
@@ -178,6 +179,7 @@ impl eframe::App for App {
   }
 
   fn update(&mut self, ctx: &Context, _: &mut eframe::Frame) {
+    self.theme.update_environment(ctx);
     self.scrolling_necessary = false;
     self.consume_events();
 
@@ -190,18 +192,39 @@ impl eframe::App for App {
     // │~~~~~~~│         │
     // └───────┴─────────┘
 
+    let errors = self.theme.errors();
+    if !errors.is_empty() {
+      let elt = elt::panel().id("theme-errors");
+      let celt = elt.compute(&self.theme.rules()).available_size(ctx.available_rect().size());
+      let frame = celt.clone().into();
+      let panel = TopBottomPanel::top("main::theme-errors")
+        .frame(frame)
+        .show(ctx, |ui| ui::theme_errors::update(ui, &self.theme.rules(), &elt, &errors));
+      ui::inspector::record(ctx, panel.response.rect, &celt);
+      if panel.inner {
+        self.theme.dismiss_errors();
+      }
+    }
+
     // FIXME: put a lot more in ui::mailboxes
     if self.show_sidebar {
-      let frame = elt::panel().id("mailboxespanel").compute(&self.theme.rules()).into();
-      SidePanel::left("main::left-side-panel").frame(frame).show(ctx, |ui| {
+      let celt = elt::panel()
+        .id("mailboxespanel")
+        .compute(&self.theme.rules())
+        .available_size(ctx.available_rect().size());
+      let frame = celt.clone().into();
+      let panel = SidePanel::left("main::left-side-panel").frame(frame).show(ctx, |ui| {
         ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
           if self.mboxes.is_empty() {
             ui.centered_and_justified(|ui| ui.spinner());
           } else {
             // FIXME: return None if no NEW mailbox is selected.
             // FIXME: that's ugly
-            if let Some(mbox) = ui::mailboxes::update(ui, &self.theme.rules(), &self.mboxes, &self.selected_mbox) {
+            // The rules are locked until the end of the statement.
+            let selected = ui::mailboxes::update(ui, &self.theme.rules(), &self.mboxes, &self.selected_mbox);
+            if let Some(mbox) = selected {
               self.selected_mbox = Some(mbox.clone());
+              self.theme.set_account(ui.ctx(), Some(&mbox.account));
               self.scrolling_necessary = false;
               self.selected_row = None;
               self.envelopes.clear();
@@ -212,6 +235,7 @@ impl eframe::App for App {
           }
         });
       });
+      ui::inspector::record(ctx, panel.response.rect, &celt);
     }
 
     // ┌───────┬─────────┐
@@ -228,10 +252,10 @@ impl eframe::App for App {
 
       let mut elt = elt::panel().id("mailbodypanel");
       elt.toggle_class("sidebaropen", self.show_sidebar);
-      let computed = elt.compute(&self.theme.rules());
+      let computed = elt.compute(&self.theme.rules()).available_size(ctx.available_rect().size());
       let frame = computed.clone().into();
-      let box_props: BoxProperties = computed.into();
-      TopBottomPanel::bottom("my_panel")
+      let box_props: BoxProperties = computed.clone().into();
+      let panel = TopBottomPanel::bottom("my_panel")
         .frame(frame)
         .min_height(box_props.min_height)
         .default_height(box_props.height)
@@ -239,6 +263,7 @@ impl eframe::App for App {
         .show(ctx, |ui| {
           ui::mailbody::update(ui, &self.theme.rules(), envelope, self.bodies.get(&envelope.id));
         });
+      ui::inspector::record(ctx, panel.response.rect, &computed);
     }
 
     // ┌───────┬─────────┐
@@ -252,17 +277,22 @@ impl eframe::App for App {
 
     let mut elt = elt::panel().id("mainpanel");
     elt.toggle_class("sidebaropen", self.show_sidebar);
-    let frame = elt.compute(&self.theme.rules()).into();
-    CentralPanel::default().frame(frame).show(ctx, |ui| {
+    let celt = elt.compute(&self.theme.rules()).available_size(ctx.available_rect().size());
+    let frame = celt.clone().into();
+    let panel = CentralPanel::default().frame(frame).show(ctx, |ui| {
       self.consume_keys(ui);
       ui.vertical(|ui| {
-        let button_clicked = ui::toolbar::update(ui, &self.theme.rules(), &elt);
+        let mut appearance = self.theme.appearance();
+        let button_clicked = ui::toolbar::update(ui, &self.theme.rules(), &elt, &mut appearance);
         if button_clicked {
           self.show_sidebar = !self.show_sidebar;
         }
+        if appearance != self.theme.appearance() {
+          self.theme.set_appearance(ui.ctx(), appearance);
+        }
         if self.selected_mbox.is_none() {
           ui.centered_and_justified(|ui| {
-            let celt = elt::label().id("no-mailbox-label").compute(&self.theme.rules());
+            let celt = elt::label().id("no-mailbox-label").parent(elt.clone()).compute(&self.theme.rules());
             let label = Label::new(&celt, "No mailbox selected");
             ui.add(label);
           });
@@ -276,5 +306,8 @@ impl eframe::App for App {
         }
       });
     });
+    ui::inspector::record(ctx, panel.response.rect, &celt);
+
+    ui::inspector::show(ctx, &self.theme.rules());
   }
 }
@@ -3,6 +3,7 @@
 #![feature(result_option_inspect)]
 
 mod app;
+mod options;
 mod server;
 mod theme;
 mod ui;
@@ -10,6 +11,19 @@ mod ui;
 fn main() {
   pretty_env_logger::init();
 
+  let app_options = match options::parse_args() {
+    Ok(options) => options,
+    Err(e) => {
+      eprintln!("Error: {}", e);
+      #[allow(clippy::exit)]
+      std::process::exit(2);
+    },
+  };
+  if app_options.help {
+    println!("{}", options::USAGE);
+    return;
+  }
+
   let options = eframe::NativeOptions {
     // Generally, looks better. But NSVisualEffectView doesn't work.
     // Also fixes https://github.com/emilk/egui/issues/903
@@ -26,8 +40,8 @@ fn main() {
   eframe::run_native(
     "Himalaya",
     options,
-    Box::new(|cc| {
-      match app::App::new(cc) {
+    Box::new(move |cc| {
+      match app::App::new(cc, &app_options) {
         Ok(app) => Box::new(app),
         Err(e) => {
           eprintln!("Error: {}", e);
@@ -0,0 +1,40 @@
+//! Command-line options.
+//!
+//! ```text
+//! himalaya-gui [--theme NAME] [--theme-path PATH] [--help]
+//! ```
+//!
+//! `--theme` applies a bundled theme on top of the default one. `--theme-path`
+//! overrides the user theme, otherwise read from `$HIMALAYA_GUI_THEME`, the
+//! `theme` path of `config.toml` in the config directory, or `theme.css` in
+//! the XDG config directories.
+
+use std::path::PathBuf;
+
+use anyhow::{anyhow, bail, Result};
+
+pub const USAGE: &str = "Usage: himalaya-gui [--theme NAME] [--theme-path PATH] [--help]";
+
+#[derive(Debug, Default)]
+pub struct Options {
+  pub theme_name: Option<String>,
+  pub theme_path: Option<PathBuf>,
+  /// `--help` or `-h`: print [USAGE] and exit.
+  pub help: bool,
+}
+
+pub fn parse_args() -> Result<Options> {
+  let mut options = Options::default();
+  let mut args = std::env::args().skip(1);
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--theme" => options.theme_name = Some(args.next().ok_or_else(|| anyhow!("--theme needs a value"))?),
+      "--theme-path" => options.theme_path = Some(args.next().ok_or_else(|| anyhow!("--theme-path needs a value"))?.into()),
+      "--help" | "-h" => options.help = true,
+      // Process serial number, passed by macOS to apps launched from the Finder.
+      _ if arg.starts_with("-psn_") => {},
+      _ => bail!("Unknown option: {}\n{}", arg, USAGE),
+    }
+  }
+  Ok(options)
+}
@@ -1,77 +1,549 @@
-use std::path::Path;
-use std::sync::Arc;
-
-use anyhow::Result;
-use css::{parse_css, Rules};
-use egui::style::{Interaction, Margin, Spacing, Style, Visuals, Widgets};
-use egui::{epaint, vec2, Context, Frame};
-use notify::event::{DataChange, EventKind, ModifyKind};
-use notify::{FsEventWatcher, RecursiveMode, Watcher};
-use parking_lot::{MappedRwLockReadGuard, RwLock, RwLockReadGuard};
-use styling::{element as elt, fonts, TextColors};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Weak};
+use std::time::Duration;
+use std::{fmt, thread};
+
+use anyhow::{anyhow, bail, Context as _, Result};
+use css::{parse_stylesheets, ColorScheme, Diagnostic, Environment, Rules, Severity, Stylesheet};
+use egui::style::{Style, Visuals, Widgets};
+use egui::{Context, Frame};
+use log::{error, info, warn};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use parking_lot::{MappedRwLockReadGuard, Mutex, RwLock, RwLockReadGuard};
+use styling::{element as elt, fonts, NativeSizes, TextColors};
+
+use crate::options::Options;
 
 // FIXME:Make it so that we don't need to hold a reference to watchers
 // FIXME: and only carry the rules around.
 pub struct Theme {
-  rules: Arc<RwLock<Rules>>,
-  _watcher: FsEventWatcher,
+  state: Arc<RwLock<State>>,
+  appearance: Appearance,
+  dark: bool,
+  // Last detected OS color scheme, and the detector thread. See [detect].
+  system_dark: bool,
+  detected: mpsc::Receiver<bool>,
+  detect_now: mpsc::Sender<()>,
+  focused: bool,
+  // The default and bundled themes, and the user theme. See [layer_paths].
+  base: Vec<Stylesheet>,
+  user_theme: Option<PathBuf>,
+  account: Option<String>,
+  // Shared with the reload thread, which watches new directories.
+  watcher: Arc<Mutex<RecommendedWatcher>>,
+}
+
+// Shared with the watcher.
+struct State {
+  rules: Rules,
+  // Why the last load failed. The last good rules are kept meanwhile.
+  errors: Vec<ThemeError>,
+  // The layers on top of the base ones, [canonical].
+  paths: Vec<PathBuf>,
+}
+
+/// A theme layer that can't be read, or an error in it.
+#[derive(Debug, Clone)]
+pub struct ThemeError {
+  pub origin: String,
+  /// `None` if the file can't be read.
+  pub line: Option<usize>,
+  pub message: String,
+}
+
+impl fmt::Display for ThemeError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self.line {
+      Some(line) => write!(f, "{}:{}: {}", self.origin, line, self.message),
+      None => write!(f, "{}: {}", self.origin, self.message),
+    }
+  }
+}
+
+impl From<Diagnostic> for ThemeError {
+  fn from(diagnostic: Diagnostic) -> Self {
+    let message = if diagnostic.snippet.is_empty() {
+      diagnostic.message
+    } else {
+      format!("{} `{}`", diagnostic.message, diagnostic.snippet)
+    };
+    ThemeError {
+      origin: diagnostic.origin,
+      line: Some(diagnostic.line),
+      message,
+    }
+  }
+}
+
+/// The color scheme chosen by the user. `Auto` follows the OS.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Appearance {
+  #[default]
+  Auto,
+  Light,
+  Dark,
+}
+
+// How often the OS color scheme is detected, besides when the window gets
+// the focus.
+const DETECTION_INTERVAL: Duration = Duration::from_secs(5);
+
+// Quiet time after a file event before reloading.
+const DEBOUNCE_DELAY: Duration = Duration::from_millis(100);
+
+// Whether `event` creates, changes, renames or removes one of the layers, or
+// one of their directories. `paths` must be [canonical], like the event ones.
+fn is_layer_change(event: &Event, paths: &[PathBuf]) -> bool {
+  matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)) &&
+    event.paths.iter().any(|changed| paths.iter().any(|path| path.starts_with(changed)))
+}
+
+// Absolute, with the symbolic links of its existing part resolved, so that it
+// can be compared with the paths of the watcher events.
+fn canonical(path: &Path) -> PathBuf {
+  let path = std::env::current_dir().map_or_else(|_| path.to_path_buf(), |dir| dir.join(path));
+  for ancestor in path.ancestors() {
+    if let Ok(canonical) = ancestor.canonicalize() {
+      let rest = path.strip_prefix(ancestor).unwrap_or(Path::new(""));
+      return if rest.as_os_str().is_empty() { canonical } else { canonical.join(rest) };
+    }
+  }
+  path
+}
+
+// Editors often save by writing another file and renaming it, so the
+// directories are watched rather than the files. A missing directory is
+// watched through its nearest existing ancestor, until it is created. Watching
+// a directory again is harmless.
+fn watch_layers(watcher: &mut RecommendedWatcher, paths: &[PathBuf]) {
+  let mut watched: Vec<&Path> = paths.iter().filter_map(|path| path.ancestors().skip(1).find(|dir| dir.is_dir())).collect();
+  watched.sort();
+  watched.dedup();
+  for dir in watched {
+    if let Err(e) = watcher.watch(dir, RecursiveMode::NonRecursive) {
+      info!("Theme hot reload disabled for {}. Can't watch it: {}", dir.display(), e);
+    }
+  }
+}
+
+// Always used as the base layer. The other layers are applied on top of it.
+const DEFAULT_THEME: &str = include_str!("../../../theme.css");
+
+fn detect_dark() -> bool {
+  matches!(dark_light::detect(), dark_light::Mode::Dark)
+}
+
+// Detecting can take a while, so it runs in a thread. It sends whether the OS
+// color scheme is dark when that changes from `dark`. Detects every
+// `DETECTION_INTERVAL`, or when woken. Stops once `Theme` is dropped.
+fn detect(ctx: Context, mut dark: bool) -> (mpsc::Receiver<bool>, mpsc::Sender<()>) {
+  let (sender, detected) = mpsc::channel();
+  let (detect_now, wake) = mpsc::channel();
+  thread::spawn(move || {
+    loop {
+      match wake.recv_timeout(DETECTION_INTERVAL) {
+        Ok(()) | Err(mpsc::RecvTimeoutError::Timeout) => {},
+        Err(mpsc::RecvTimeoutError::Disconnected) => return,
+      }
+      let detected = detect_dark();
+      if detected != dark {
+        dark = detected;
+        if sender.send(dark).is_err() {
+          return;
+        }
+        ctx.request_repaint();
+      }
+    }
+  });
+  (detected, detect_now)
+}
+
+fn get_system_classes(dark: bool) -> [&'static str; 2] {
+  [std::env::consts::OS, if dark { "dark" } else { "light" }]
+}
+
+fn get_environment(ctx: &Context, dark: bool) -> Environment {
+  let size = ctx.input().screen_rect().size();
+  Environment {
+    width: size.x,
+    height: size.y,
+    color_scheme: if dark { ColorScheme::Dark } else { ColorScheme::Light },
+    os: std::env::consts::OS.into(),
+  }
+}
+
+// Bundled themes, selected with `--theme NAME`. Applied on top of the default
+// theme.
+const BUNDLED_THEMES: [(&str, &str); 2] = [
+  ("compact", include_str!("../../../themes/compact.css")),
+  ("high-contrast", include_str!("../../../themes/high-contrast.css")),
+];
+
+const THEME_ENV: &str = "HIMALAYA_GUI_THEME";
+
+fn app_config_dir() -> Option<PathBuf> {
+  dirs::config_dir().map(|dir| dir.join("himalaya-gui"))
+}
+
+// `$XDG_CONFIG_HOME`, then `$XDG_CONFIG_DIRS`.
+fn app_config_dirs() -> Vec<PathBuf> {
+  let mut list: Vec<PathBuf> = dirs::config_dir().into_iter().collect();
+  match std::env::var_os("XDG_CONFIG_DIRS") {
+    Some(paths) => list.extend(std::env::split_paths(&paths)),
+    None if cfg!(target_os = "linux") => list.push("/etc/xdg".into()),
+    None => {},
+  }
+  list.into_iter().map(|dir| dir.join("himalaya-gui")).collect()
+}
+
+// Themes given explicitly must exist.
+fn existing(path: PathBuf, source: &str) -> Result<PathBuf> {
+  if !path.is_file() {
+    bail!("Theme file not found: {} (from {})", path.display(), source);
+  }
+  Ok(path)
+}
+
+// `theme` in `config.toml`, relative to the config directory.
+fn config_theme_path() -> Result<Option<PathBuf>> {
+  let dir = match app_config_dir() {
+    Some(dir) => dir,
+    None => return Ok(None),
+  };
+  let path = dir.join("config.toml");
+  if !path.exists() {
+    return Ok(None);
+  }
+  let source = std::fs::read_to_string(&path).with_context(|| format!("Can't read {}", path.display()))?;
+  let config: toml::Value = source.parse().with_context(|| format!("Invalid {}", path.display()))?;
+  match config.get("theme") {
+    None => Ok(None),
+    Some(toml::Value::String(theme)) => existing(dir.join(theme), &path.display().to_string()).map(Some),
+    Some(_) => bail!("Invalid {}: `theme` must be a path", path.display()),
+  }
+}
+
+// From `--theme-path`, `$HIMALAYA_GUI_THEME`, `theme` in `config.toml`, or
+// else `theme.css` in the XDG config directories, in that order.
+fn user_theme_path(options: &Options) -> Result<Option<PathBuf>> {
+  if let Some(path) = &options.theme_path {
+    return existing(path.clone(), "--theme-path").map(Some);
+  }
+  if let Some(path) = std::env::var_os(THEME_ENV).filter(|path| !path.is_empty()) {
+    return existing(path.into(), &format!("${}", THEME_ENV)).map(Some);
+  }
+  if let Some(path) = config_theme_path()? {
+    return Ok(Some(path));
+  }
+  let candidates: Vec<PathBuf> = app_config_dirs().into_iter().map(|dir| dir.join("theme.css")).collect();
+  // If there is none, the first one is still watched, to load it once
+  // created.
+  Ok(candidates.iter().find(|path| path.is_file()).or_else(|| candidates.first()).cloned())
+}
+
+// The default theme, then the bundled theme selected with `--theme`. Their
+// relative urls are resolved against the config directory.
+fn base_layers(options: &Options) -> Result<Vec<Stylesheet>> {
+  let embedded = |origin: &str, source: &str| {
+    Stylesheet {
+      dir: app_config_dir(),
+      ..Stylesheet::new(origin, source)
+    }
+  };
+  let mut layers = vec![embedded("<default>", DEFAULT_THEME)];
+  if let Some(name) = &options.theme_name {
+    let (_, source) = BUNDLED_THEMES.iter().find(|(bundled, _)| bundled == name).ok_or_else(|| {
+      let names: Vec<&str> = BUNDLED_THEMES.iter().map(|(name, _)| *name).collect();
+      anyhow!("Unknown theme `{}`. Bundled themes: {}", name, names.join(", "))
+    })?;
+    layers.push(embedded(&format!("<{}>", name), source));
+  }
+  Ok(layers)
+}
+
+// The layers applied on top of the base ones, by increasing precedence: the
+// user theme, then the override of the selected account, in `config_dir`.
+fn layer_paths(user_theme: Option<&Path>, config_dir: Option<&Path>, account: Option<&str>) -> Vec<PathBuf> {
+  let mut paths: Vec<PathBuf> = user_theme.into_iter().map(Path::to_path_buf).collect();
+  match (config_dir, account) {
+    (Some(dir), Some(account)) => paths.push(dir.join("accounts").join(format!("{}.css", account))),
+    (None, Some(_)) => warn!("No config directory. Per-account themes are disabled"),
+    (_, None) => {},
+  }
+  paths.iter().map(|path| canonical(path)).collect()
 }
 
-const CSS_PATH: &str = "./theme.css";
+// The base layers, then the other ones. Missing layers are skipped.
+fn read_layers(base: &[Stylesheet], paths: &[PathBuf]) -> Result<Vec<Stylesheet>, Vec<ThemeError>> {
+  let mut stylesheets = base.to_vec();
+  let mut errors = Vec::new();
+  for path in paths {
+    if !path.exists() {
+      info!("No theme layer at {}", path.display());
+      continue;
+    }
+    match Stylesheet::from_path(path) {
+      Ok(stylesheet) => stylesheets.push(stylesheet),
+      Err(e) => {
+        errors.push(ThemeError {
+          origin: path.display().to_string(),
+          line: None,
+          message: e.to_string(),
+        })
+      },
+    }
+  }
+  if errors.is_empty() {
+    Ok(stylesheets)
+  } else {
+    Err(errors)
+  }
+}
 
-fn get_system_classes() -> [&'static str; 2] {
-  match dark_light::detect() {
-    dark_light::Mode::Dark => [std::env::consts::OS, "dark"],
-    dark_light::Mode::Light => [std::env::consts::OS, "light"],
+// Only fails on errors. Warnings are logged.
+fn parse_theme(stylesheets: Vec<Stylesheet>, dark: bool) -> Result<Rules, Vec<ThemeError>> {
+  let (rules, diagnostics) = parse_stylesheets(stylesheets, &get_system_classes(dark));
+  let mut errors = Vec::new();
+  for diagnostic in diagnostics {
+    match diagnostic.severity {
+      Severity::Error => errors.push(diagnostic.into()),
+      Severity::Warning => warn!("{}", diagnostic),
+    }
+  }
+  if errors.is_empty() {
+    Ok(rules)
+  } else {
+    Err(errors)
+  }
+}
+
+fn load_theme(base: &[Stylesheet], paths: &[PathBuf], dark: bool) -> Result<Rules, Vec<ThemeError>> {
+  parse_theme(read_layers(base, paths)?, dark)
+}
+
+impl State {
+  fn apply(&mut self, ctx: &Context, mut rules: Rules, environment: Environment) {
+    let dark = environment.color_scheme == ColorScheme::Dark;
+    rules.set_environment(environment);
+    fonts::register(ctx, &rules);
+    update_native_style(ctx, &rules, dark);
+    self.rules = rules;
+    ctx.request_repaint();
+  }
+
+  fn fail(&mut self, ctx: &Context, errors: Vec<ThemeError>) {
+    for error in &errors {
+      error!("{}", error);
+    }
+    self.errors = errors;
+    ctx.request_repaint();
   }
 }
 
 impl Theme {
-  pub fn init(cc: &eframe::CreationContext<'_>) -> Result<Theme> {
+  /// If the layers fail to load, the default and bundled themes are used until
+  /// they are fixed. Fails if a theme given in `options`, the environment or
+  /// the config file doesn't exist. No account is selected yet, see
+  /// [Theme::set_account].
+  pub fn init(cc: &eframe::CreationContext<'_>, options: &Options) -> Result<Theme> {
     let ctx = &cc.egui_ctx;
 
-    let dark = matches!(dark_light::detect(), dark_light::Mode::Dark);
+    let dark = detect_dark();
 
-    let rules = parse_css(CSS_PATH, &get_system_classes())?;
-    let rules = Arc::new(RwLock::new(rules));
+    let base = base_layers(options)?;
+    let user_theme = user_theme_path(options)?;
+    let paths = layer_paths(user_theme.as_deref(), app_config_dir().as_deref(), None);
+    let (mut rules, errors) = match load_theme(&base, &paths, dark) {
+      Ok(rules) => (rules, Vec::new()),
+      Err(errors) => {
+        for error in &errors {
+          error!("{}", error);
+        }
+        (parse_stylesheets(base.clone(), &get_system_classes(dark)).0, errors)
+      },
+    };
+    rules.set_environment(get_environment(ctx, dark));
+    fonts::register(ctx, &rules);
+    update_native_style(ctx, &rules, dark);
+    let state = Arc::new(RwLock::new(State {
+      rules,
+      errors,
+      paths: paths.clone(),
+    }));
+
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    watch_layers(&mut watcher, &paths);
+    let watcher = Arc::new(Mutex::new(watcher));
+    let inner_watcher: Weak<Mutex<RecommendedWatcher>> = Arc::downgrade(&watcher);
 
-    let inner_rules = rules.clone();
+    let inner_state = state.clone();
     let inner_ctx = cc.egui_ctx.clone();
-    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
-      match res {
-        Ok(e) => {
-          if matches!(e.kind, EventKind::Modify(ModifyKind::Data(DataChange::Content))) {
-            let rules = parse_css(CSS_PATH, &get_system_classes()).unwrap(); // FIXME
-            let mut inner_rules = inner_rules.write();
-            *inner_rules = rules;
-            update_native_style(&inner_ctx, &inner_rules, dark);
-            inner_ctx.request_repaint();
+    let inner_base = base.clone();
+    thread::spawn(move || {
+      while let Ok(res) = receiver.recv() {
+        match res {
+          Ok(e) if is_layer_change(&e, &inner_state.read().paths) => {},
+          Ok(_) => continue,
+          Err(e) => {
+            error!("Theme watcher error: {}", e);
+            continue;
+          },
+        }
+        // Saves come in bursts of events. Wait for the last one.
+        while receiver.recv_timeout(DEBOUNCE_DELAY).is_ok() {}
+        // A missing directory might have been created.
+        let paths = inner_state.read().paths.clone();
+        match inner_watcher.upgrade() {
+          Some(watcher) => watch_layers(&mut watcher.lock(), &paths),
+          None => return,
+        }
+
+        // The color scheme and the account can change while parsing, so they
+        // are checked again before applying.
+        loop {
+          let (dark, paths) = {
+            let state = inner_state.read();
+            (state.rules.environment().color_scheme == ColorScheme::Dark, state.paths.clone())
+          };
+          let loaded = load_theme(&inner_base, &paths, dark);
+          let mut state = inner_state.write();
+          let environment = state.rules.environment().clone();
+          if (environment.color_scheme == ColorScheme::Dark) != dark || state.paths != paths {
+            continue;
           }
-        },
-        Err(e) => {
-          println!("watch error: {:?}", e);
-        },
+          match loaded {
+            Ok(rules) => {
+              state.apply(&inner_ctx, rules, environment);
+              state.errors.clear();
+            },
+            Err(errors) => state.fail(&inner_ctx, errors),
+          }
+          break;
+        }
       }
-    })?;
-    watcher.watch(Path::new(CSS_PATH), RecursiveMode::NonRecursive)?;
+    });
+
+    let (detected, detect_now) = detect(cc.egui_ctx.clone(), dark);
+    Ok(Theme {
+      watcher,
+      base,
+      user_theme,
+      account: None,
+      state,
+      appearance: Appearance::Auto,
+      dark,
+      system_dark: dark,
+      detected,
+      detect_now,
+      focused: true,
+    })
+  }
+
+  /// Re-evaluates the `@media` blocks. Call on every frame, as the window
+  /// might have been resized. Also enables the newly loaded fonts, and follows
+  /// the OS color scheme.
+  pub fn update_environment(&mut self, ctx: &Context) {
+    fonts::sync(ctx);
+    self.follow_system(ctx);
+    let environment = get_environment(ctx, self.dark);
+    if *self.rules().environment() == environment {
+      return;
+    }
+    let mut state = self.state.write();
+    if state.rules.set_environment(environment) {
+      update_native_style(ctx, &state.rules, self.dark);
+    }
+  }
 
-    let theme = Theme { _watcher: watcher, rules };
+  /// Selects the layer of `account`, `accounts/<name>.css` in the config
+  /// directory, and reloads the theme.
+  pub fn set_account(&mut self, ctx: &Context, account: Option<&str>) {
+    if self.account.as_deref() == account {
+      return;
+    }
+    self.account = account.map(str::to_owned);
+    let paths = layer_paths(self.user_theme.as_deref(), app_config_dir().as_deref(), account);
+    watch_layers(&mut self.watcher.lock(), &paths);
+    let loaded = load_theme(&self.base, &paths, self.dark);
+    let mut state = self.state.write();
+    state.paths = paths;
+    match loaded {
+      Ok(rules) => {
+        state.apply(ctx, rules, get_environment(ctx, self.dark));
+        state.errors.clear();
+      },
+      Err(errors) => state.fail(ctx, errors),
+    }
+  }
+
+  pub fn appearance(&self) -> Appearance {
+    self.appearance
+  }
 
-    fonts::register(ctx);
+  pub fn set_appearance(&mut self, ctx: &Context, appearance: Appearance) {
+    self.appearance = appearance;
+    let dark = match appearance {
+      Appearance::Auto => {
+        // Meanwhile, the last detected color scheme.
+        let _ = self.detect_now.send(());
+        self.system_dark
+      },
+      Appearance::Light => false,
+      Appearance::Dark => true,
+    };
+    self.set_dark(ctx, dark);
+  }
 
-    update_native_style(ctx, &theme.rules.read(), dark);
+  // Detects the OS color scheme again when the window gets the focus. With
+  // `Appearance::Auto`, applies the detected changes.
+  fn follow_system(&mut self, ctx: &Context) {
+    let focused = ctx.input().raw.has_focus;
+    if focused && !self.focused {
+      let _ = self.detect_now.send(());
+    }
+    self.focused = focused;
+    let mut changed = false;
+    while let Ok(dark) = self.detected.try_recv() {
+      self.system_dark = dark;
+      changed = true;
+    }
+    if changed && self.appearance == Appearance::Auto {
+      self.set_dark(ctx, self.system_dark);
+    }
+  }
 
-    Ok(theme)
+  // The variables depend on the color scheme, through the system classes, so
+  // the last good stylesheets are parsed again.
+  fn set_dark(&mut self, ctx: &Context, dark: bool) {
+    if dark == self.dark {
+      return;
+    }
+    let stylesheets = self.rules().stylesheets().to_vec();
+    match parse_theme(stylesheets, dark) {
+      Ok(rules) => {
+        self.state.write().apply(ctx, rules, get_environment(ctx, dark));
+        self.dark = dark;
+      },
+      Err(errors) => self.state.write().fail(ctx, errors),
+    }
   }
 
   pub fn rules(&self) -> MappedRwLockReadGuard<'_, Rules> {
-    RwLockReadGuard::map(self.rules.read(), |r| r)
+    RwLockReadGuard::map(self.state.read(), |state| &state.rules)
+  }
+
+  /// Why the last reload failed, until the next successful one.
+  pub fn errors(&self) -> Vec<ThemeError> {
+    self.state.read().errors.clone()
+  }
+
+  pub fn dismiss_errors(&self) {
+    self.state.write().errors.clear();
   }
 }
 
 fn update_native_style(ctx: &Context, rules: &Rules, dark: bool) {
-  let native_celt = elt::native().compute(rules);
-
   let hyperlink_props: TextColors = elt::native().classes("hyperlink").compute(rules).into();
   let faint_props: TextColors = elt::native().classes("faint").compute(rules).into();
   let extreme_props: TextColors = elt::native().classes("extreme").compute(rules).into();
@@ -79,37 +551,20 @@ fn update_native_style(ctx: &Context, rules: &Rules, dark: bool) {
   let warn_props: TextColors = elt::native().classes("warn").compute(rules).into();
   let error_props: TextColors = elt::native().classes("error").compute(rules).into();
   let window_props: Frame = elt::window().compute(rules).into();
+  let popup_props: Frame = elt::native().classes("popup").compute(rules).into();
+  let animation_time = elt::native().compute(rules).props().transition.duration();
+  let spacing = elt::spacing().compute(rules);
+  let sizes: NativeSizes = spacing.clone().into();
 
-  let text_styles = fonts::text_styles_for_size(native_celt.props().font_size);
+  let text_styles = fonts::text_styles_for_size(rules.root_font_size());
 
   ctx.set_style(Style {
     text_styles,
     override_font_id: None,
     override_text_style: None,
     wrap: None,
-    // FIXME: not stylable yet
-    spacing: Spacing {
-      item_spacing: vec2(0.0, 0.0),
-      window_margin: Margin::same(6.0),
-      button_padding: vec2(4.0, 1.0),
-      indent: 18.0, // match checkbox/radio-button with `button_padding.x + icon_width + icon_spacing`
-      interact_size: vec2(40.0, 18.0),
-      slider_width: 100.0,
-      text_edit_width: 280.0,
-      icon_width: 14.0,
-      icon_width_inner: 8.0,
-      icon_spacing: 4.0,
-      tooltip_width: 600.0,
-      combo_height: 200.0,
-      scroll_bar_width: 4.0, // FIXME: need to be themable
-      indent_ends_with_horizontal_line: false,
-      menu_margin: Margin::same(1.0),
-    },
-    interaction: Interaction {
-      resize_grab_radius_side: 5.0,
-      resize_grab_radius_corner: 10.0,
-      show_tooltips_only_when_still: false,
-    },
+    spacing: spacing.clone().into(),
+    interaction: spacing.into(),
     visuals: Visuals {
       dark_mode: dark,
       override_text_color: None,
@@ -122,12 +577,12 @@ fn update_native_style(ctx: &Context, rules: &Rules, dark: bool) {
       },
       selection: elt::native().classes("selection").compute(rules).into(),
 
-      window_shadow: epaint::Shadow::big_dark(),
-      popup_shadow: epaint::Shadow::small_dark(),
-      resize_corner_size: 12.0,
-      text_cursor_width: 2.0,
+      window_shadow: window_props.shadow,
+      popup_shadow: popup_props.shadow,
+      resize_corner_size: sizes.resize_corner_size,
+      text_cursor_width: sizes.text_cursor_width,
       text_cursor_preview: false,
-      clip_rect_margin: 0.0,
+      clip_rect_margin: sizes.clip_rect_margin,
       button_frame: true,
       collapsing_header_frame: false,
 
@@ -139,8 +594,53 @@ fn update_native_style(ctx: &Context, rules: &Rules, dark: bool) {
       error_fg_color: error_props.fg,
       window_rounding: window_props.rounding,
     },
-    animation_time: 1.0 / 12.0,
+    animation_time,
     debug: Default::default(),
     explanation_tooltips: false,
   });
 }
+
+#[cfg(test)]
+mod tests {
+  use css::Length;
+
+  use super::*;
+
+  // A config directory with a user theme and the layers of two accounts.
+  fn config_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("himalaya-gui-layers-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("accounts")).unwrap();
+    std::fs::write(dir.join("theme.css"), "label { width: 1; height: 1; }").unwrap();
+    std::fs::write(dir.join("accounts").join("work.css"), "label { width: 2; }").unwrap();
+    std::fs::write(dir.join("accounts").join("home.css"), "label { width: 3; min-height: 3; }").unwrap();
+    dir
+  }
+
+  #[test]
+  fn layers() {
+    let dir = config_dir();
+    let user_theme = dir.join("theme.css");
+    assert_eq!(layer_paths(Some(&user_theme), Some(&dir), None), [canonical(&user_theme)]);
+    assert_eq!(
+      layer_paths(Some(&user_theme), Some(&dir), Some("work")),
+      [canonical(&user_theme), canonical(&dir.join("accounts").join("work.css"))]
+    );
+    assert_eq!(layer_paths(None, None, Some("work")), Vec::<PathBuf>::new());
+
+    let base = [Stylesheet::new("<default>", "label { width: 0; height: 0; min-height: 0; }")];
+    let label = |account: Option<&str>| {
+      let rules = load_theme(&base, &layer_paths(Some(&user_theme), Some(&dir), account), false).unwrap();
+      let props = elt::label().compute(&rules).props().clone();
+      [props.width, props.height, props.min_height]
+    };
+    // Each layer overrides the previous ones.
+    assert_eq!(label(None), [Length::Px(1.0), Length::Px(1.0), Length::Px(0.0)]);
+    assert_eq!(label(Some("work")), [Length::Px(2.0), Length::Px(1.0), Length::Px(0.0)]);
+    // Only the layer of the selected account applies.
+    assert_eq!(label(Some("home")), [Length::Px(3.0), Length::Px(1.0), Length::Px(3.0)]);
+    // Missing layers are skipped.
+    assert_eq!(label(Some("other")), [Length::Px(1.0), Length::Px(1.0), Length::Px(0.0)]);
+
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+}
@@ -0,0 +1,126 @@
+//! Lays out children along the `direction` of a `vbox` or `hbox`, following
+//! its `gap` and `justify-content`, and their `flex-grow` and `display`.
+//! Like CSS blocks, containers take the full available width.
+//!
+//! Children are measured during the previous frame, so a change of size takes
+//! an extra frame to settle.
+
+use css::{Justify, Rules};
+use egui::{vec2, Align, Frame, Id, Layout, Rect, Response, Sense, Ui, Vec2};
+use styling::element::Element;
+use styling::LayoutProperties;
+
+use crate::ui::inspector;
+
+type AddContents<'a> = Box<dyn FnOnce(&mut Ui) + 'a>;
+
+#[must_use = "You should show this container with `container.show(ui);`"]
+pub struct Container<'a> {
+  rules: &'a Rules,
+  elt: Element,
+  children: Vec<(LayoutProperties, AddContents<'a>)>,
+}
+
+impl<'a> Container<'a> {
+  pub fn new(rules: &'a Rules, elt: &Element) -> Self {
+    Self {
+      rules,
+      elt: elt.clone(),
+      children: Vec::new(),
+    }
+  }
+
+  /// `elt` provides the `display` and `flex-grow` of the child.
+  pub fn child(mut self, elt: &Element, add_contents: impl FnOnce(&mut Ui) + 'a) -> Self {
+    let layout: LayoutProperties = elt.compute(self.rules).into();
+    if layout.visible {
+      self.children.push((layout, Box::new(add_contents)));
+    }
+    self
+  }
+
+  pub fn show(self, ui: &mut Ui) -> Response {
+    let celt = self.elt.compute(self.rules).available_size(ui.available_size());
+    let layout: LayoutProperties = celt.clone().into();
+    if !layout.visible {
+      return ui.allocate_response(Vec2::ZERO, Sense::hover());
+    }
+    let frame: Frame = celt.clone().into();
+    let id = ui.make_persistent_id(&self.elt);
+    let response = frame.show(ui, |ui| lay_out(ui, id, &layout, self.children)).response;
+    inspector::record(ui.ctx(), response.rect, &celt);
+    response
+  }
+}
+
+fn lay_out(ui: &mut Ui, id: Id, layout: &LayoutProperties, children: Vec<(LayoutProperties, AddContents<'_>)>) {
+  let horizontal = layout.horizontal;
+  let main = |size: Vec2| if horizontal { size.x } else { size.y };
+  let cross = |size: Vec2| if horizontal { size.y } else { size.x };
+  let from_axes = |main: f32, cross: f32| if horizontal { vec2(main, cross) } else { vec2(cross, main) };
+
+  let available = ui.available_size();
+  let previous: Vec<f32> = ui.ctx().data().get_temp(id).unwrap_or_default();
+
+  let count = children.len();
+  let grow_total: f32 = children.iter().map(|(child, _)| child.flex_grow).sum();
+  let fixed: f32 = children
+    .iter()
+    .enumerate()
+    .filter(|(_, (child, _))| child.flex_grow == 0.0)
+    .map(|(index, _)| previous.get(index).copied().unwrap_or(0.0))
+    .sum::<f32>() +
+    layout.gap * count.saturating_sub(1) as f32;
+  let free = if main(available).is_finite() {
+    (main(available) - fixed).max(0.0)
+  } else {
+    0.0
+  };
+
+  let (mut offset, gap) = match layout.justify {
+    _ if grow_total > 0.0 => (0.0, layout.gap),
+    Justify::Start => (0.0, layout.gap),
+    Justify::Center => (free / 2.0, layout.gap),
+    Justify::End => (free, layout.gap),
+    Justify::SpaceBetween if count > 1 => (0.0, layout.gap + free / (count - 1) as f32),
+    Justify::SpaceBetween => (0.0, layout.gap),
+  };
+
+  let origin = ui.available_rect_before_wrap().min;
+  let mut measured = Vec::with_capacity(count);
+  let mut cross_size: f32 = 0.0;
+  for (index, (child, add_contents)) in children.into_iter().enumerate() {
+    let grow_size = (grow_total > 0.0 && child.flex_grow > 0.0).then(|| free * child.flex_grow / grow_total);
+    let max_size = from_axes(grow_size.unwrap_or((main(available) - offset).max(0.0)), cross(available));
+    let mut child_ui = ui.child_ui(Rect::from_min_size(origin + from_axes(offset, 0.0), max_size), Layout::top_down(Align::Min));
+    if let Some(size) = grow_size {
+      if horizontal {
+        child_ui.set_min_width(size);
+      } else {
+        child_ui.set_min_height(size);
+      }
+    }
+    add_contents(&mut child_ui);
+    let size = child_ui.min_rect().size();
+    // Growing children don't count in the fixed size.
+    measured.push(if grow_size.is_some() { 0.0 } else { main(size) });
+    cross_size = cross_size.max(cross(size));
+    offset += main(size);
+    if index + 1 < count {
+      offset += gap;
+    }
+  }
+
+  // Fill the main axis when the free space is distributed.
+  let fills = grow_total > 0.0 || layout.justify != Justify::Start;
+  let mut size = from_axes(if fills && main(available).is_finite() { main(available) } else { offset }, cross_size);
+  if available.x.is_finite() {
+    size.x = available.x;
+  }
+  ui.allocate_rect(Rect::from_min_size(origin, size), Sense::hover());
+
+  if measured != previous {
+    ui.ctx().data().insert_temp(id, measured);
+    ui.ctx().request_repaint();
+  }
+}
@@ -1,18 +1,17 @@
-use css::Rules;
-use egui::{pos2, Frame, Id, Rect, ScrollArea, Sense, Ui};
+use css::{Display, Length, Rules};
+use egui::{pos2, vec2, Frame, Id, Rect, ScrollArea, Sense, Ui};
 use styling::element::{ComputedElement, Element};
 use styling::{element as elt, BoxProperties};
 
 use crate::server::{Envelope, Flag};
+use crate::ui::inspector;
 use crate::ui::label::Label;
 
 // FIXME: lot of vecs. could use arrays.
-// FIXME: A lot could be computed in `new`, but then the theme live reload would
-// not work. We need an invalidation system.
 pub fn update(ui: &mut Ui, rules: &Rules, envelopes: &Vec<Envelope>, selection: &Option<usize>, show_selection: bool) -> Option<usize> {
   let mut clicked_row = None;
 
-  let frame: Frame = elt::hbox().id("envelopes-frame").compute(rules).into();
+  let frame: Frame = elt::hbox().id("envelopes-frame").compute(rules).available_size(ui.available_size()).into();
 
   frame.show(ui, |ui| {
     let row_height = calculate_row_height(rules);
@@ -30,24 +29,13 @@ pub fn update(ui: &mut Ui, rules: &Rules, envelopes: &Vec<Envelope>, selection:
         for index in row_range {
           let envelope = &envelopes[index];
 
-          let selected = selection.map(|selection| index == selection).unwrap_or(false);
-
-          let mut row_elt = create_row_elt();
-          row_elt.toggle_class("selected", selected);
-          envelope.flags.iter().for_each(|flag| {
-            match flag {
-              Flag::Flagged => row_elt.add_class("flagged"),
-              Flag::Answered => row_elt.add_class("answered"),
-              Flag::Deleted => row_elt.add_class("deleted"),
-              Flag::Draft => row_elt.add_class("draft"),
-              Flag::Recent => row_elt.add_class("recent"),
-              Flag::Seen => row_elt.add_class("seen"),
-              Flag::Custom(_) => {},
-            };
-          });
+          let mut row_rect = ui.available_rect_before_wrap();
+          row_rect.set_height(row_height);
+          let hovered = contains_pointer(ui, row_rect.intersect(clip));
 
-          if !envelope.flags.contains(&Flag::Seen) {
-            row_elt.add_class("unread");
+          let mut row_elt = create_envelope_row_elt(envelopes, index, selection).hover(hovered);
+          if index > 0 {
+            row_elt = row_elt.prev_sibling(create_envelope_row_elt(envelopes, index - 1, selection));
           }
 
           let mut cells = create_cell_elts();
@@ -55,7 +43,9 @@ pub fn update(ui: &mut Ui, rules: &Rules, envelopes: &Vec<Envelope>, selection:
             cell.attach_parent(row_elt.clone());
           }
 
-          let props: BoxProperties = row_elt.compute(rules).into();
+          let row_id = Id::new("envelope-row").with(&envelope.id);
+          let row_celt = row_elt.compute(rules).available_size(ui.available_size()).animate(ui.ctx(), row_id);
+          let props: BoxProperties = row_celt.clone().into();
           let mut row_bg = ui.available_rect_before_wrap();
           row_bg.set_height(props.height);
 
@@ -63,27 +53,38 @@ pub fn update(ui: &mut Ui, rules: &Rules, envelopes: &Vec<Envelope>, selection:
           ui.set_clip_rect(visible_rect);
 
           ui.painter().rect(row_bg, props.rounding, props.background, props.border);
+          inspector::record(ui.ctx(), visible_rect, &row_celt);
 
           // Draw labels ========== //
 
           let labels = build_labels(envelope);
 
+          let ctx = ui.ctx().clone();
           let celts: Vec<ComputedElement> = cells
             .iter()
             .zip(&cell_widths)
-            .map(|(elt, width)| {
-              let mut celt = elt.compute(rules);
-              celt.props_mut().width = *width;
-              celt.props_mut().height = row_height;
+            .enumerate()
+            .map(|(cell_index, (elt, width))| {
+              let cell_id = row_id.with(cell_index);
+              let hovered = ctx.data().get_temp(cell_id.with("hovered")).unwrap_or(false);
+              let mut celt = elt.clone().hover(hovered).compute(rules).animate(&ctx, cell_id);
+              celt.props_mut().width = Length::Px(*width);
+              celt.props_mut().height = Length::Px(row_height);
               celt
             })
             .collect();
 
           ui.allocate_ui_at_rect(row_bg, |ui| {
             ui.horizontal(|ui| {
-              for (label, celt) in labels.iter().zip(celts) {
+              for (cell_index, (label, celt)) in labels.iter().zip(celts).enumerate() {
+                if celt.props().display == Display::None {
+                  continue;
+                }
                 let widget = Label::new(&celt, label).sense(Sense::click());
-                if ui.add(widget).clicked() {
+                let response = ui.add(widget);
+                // Cells are styled with the hover state of the previous frame.
+                ui.data().insert_temp(row_id.with(cell_index).with("hovered"), response.hovered());
+                if response.clicked() {
                   clicked_row = Some(index);
                 }
               }
@@ -98,6 +99,12 @@ pub fn update(ui: &mut Ui, rules: &Rules, envelopes: &Vec<Envelope>, selection:
   clicked_row
 }
 
+// Whether the pointer is over `rect`, and not over another layer.
+fn contains_pointer(ui: &Ui, rect: Rect) -> bool {
+  let pointer = ui.input().pointer.hover_pos();
+  pointer.map_or(false, |pos| rect.contains(pos) && ui.ctx().layer_id_at(pos) == Some(ui.layer_id()))
+}
+
 fn calculate_row_height(rules: &Rules) -> f32 {
   // Create dummy element for layout computation
   let box_props: BoxProperties = create_row_elt().compute(rules).into();
@@ -123,34 +130,76 @@ fn create_row_elt() -> Element {
   elt::hbox().classes("envelope-row")
 }
 
+fn create_envelope_row_elt(envelopes: &[Envelope], index: usize, selection: &Option<usize>) -> Element {
+  let envelope = &envelopes[index];
+  let selected = selection.map(|selection| index == selection).unwrap_or(false);
+
+  let mut row_elt = create_row_elt().position(index, envelopes.len());
+  row_elt.toggle_class("selected", selected);
+  envelope.flags.iter().for_each(|flag| {
+    match flag {
+      Flag::Flagged => row_elt.add_class("flagged"),
+      Flag::Answered => row_elt.add_class("answered"),
+      Flag::Deleted => row_elt.add_class("deleted"),
+      Flag::Draft => row_elt.add_class("draft"),
+      Flag::Recent => row_elt.add_class("recent"),
+      Flag::Seen => row_elt.add_class("seen"),
+      Flag::Custom(_) => {},
+    };
+  });
+
+  if !envelope.flags.contains(&Flag::Seen) {
+    row_elt.add_class("unread");
+  }
+  row_elt
+}
+
 fn create_cell_elts() -> Vec<Element> {
-  vec![
+  let cells = vec![
     elt::label().classes("flags-cell"),
     elt::label().classes("sender-cell"),
     elt::label().classes("subject-cell"),
     elt::label().classes("date-cell"),
-  ]
+  ];
+  let count = cells.len();
+  cells.into_iter().enumerate().map(|(index, cell)| cell.position(index, count)).collect()
 }
 
 fn calculate_cell_expansion(rules: &Rules, total_width: f32) -> Vec<f32> {
   // Dummy cells for layout computation
   let cells = create_cell_elts();
-  let props: Vec<BoxProperties> = cells.iter().map(|cell| cell.compute(rules).into()).collect();
-
-  let (non_fexible_width, flexible_count) = props.iter().fold(
-    (0.0, 0),
-    |(nfw, fc), celt| {
-      if celt.width == 0.0 {
-        (nfw, fc + 1)
-      } else {
-        (nfw + celt.width, fc)
-      }
-    },
-  );
+  let celts: Vec<ComputedElement> = cells.iter().map(|cell| cell.compute(rules).available_size(vec2(total_width, 0.0))).collect();
+  let visible: Vec<bool> = celts.iter().map(|celt| celt.props().display != Display::None).collect();
+  let props: Vec<BoxProperties> = celts.into_iter().map(Into::into).collect();
+
+  // Hidden cells take no room.
+  let (non_fexible_width, flexible_count) =
+    props.iter().zip(&visible).filter(|(_, visible)| **visible).fold(
+      (0.0, 0),
+      |(nfw, fc), (celt, _)| {
+        if celt.width == 0.0 {
+          (nfw, fc + 1)
+        } else {
+          (nfw + celt.width, fc)
+        }
+      },
+    );
 
   let flexible_width = (total_width - non_fexible_width) / flexible_count as f32;
 
-  props.iter().map(|p| if p.width == 0.0 { flexible_width } else { p.width }).collect()
+  props
+    .iter()
+    .zip(visible)
+    .map(|(p, visible)| {
+      if !visible {
+        0.0
+      } else if p.width == 0.0 {
+        flexible_width
+      } else {
+        p.width
+      }
+    })
+    .collect()
 }
 
 fn build_label_flag(envelope: &Envelope) -> String {
@@ -0,0 +1,106 @@
+//! Debug overlay, toggled with ctrl+shift+I. For the element under the
+//! pointer, shows its selector path, the rules it matched in the order they
+//! are applied, and the properties it was drawn with.
+//!
+//! Widgets [record] the elements they draw. Only the smallest recorded element
+//! under the pointer is inspected.
+
+use css::{ComputedProperties, Rules};
+use egui::{Align2, Color32, Context, Frame, Grid, Id, Key, LayerId, Modifiers, Order, Rect, RichText, Stroke};
+use styling::element::ComputedElement;
+
+#[derive(Clone, Default)]
+struct State {
+  enabled: bool,
+  // Elements drawn during the current frame, with their final properties.
+  elements: Vec<(Rect, ComputedElement)>,
+}
+
+fn id() -> Id {
+  Id::new("inspector")
+}
+
+/// Call once `celt` is drawn in `rect`, with the properties used to draw it:
+/// animated, or sized by the widget.
+pub fn record(ctx: &Context, rect: Rect, celt: &ComputedElement) {
+  let mut data = ctx.data();
+  let state = data.get_temp_mut_or_default::<State>(id());
+  if state.enabled {
+    state.elements.push((rect, celt.clone()));
+  }
+}
+
+/// Call at the end of the frame, once all the elements are recorded.
+pub fn show(ctx: &Context, rules: &Rules) {
+  let toggled = ctx.input_mut().consume_key(Modifiers::CTRL | Modifiers::SHIFT, Key::I);
+  let elements = {
+    let mut data = ctx.data();
+    let state = data.get_temp_mut_or_default::<State>(id());
+    state.enabled ^= toggled;
+    std::mem::take(&mut state.elements)
+  };
+  if toggled {
+    ctx.request_repaint();
+  }
+
+  let pointer = match ctx.input().pointer.hover_pos() {
+    Some(pointer) => pointer,
+    None => return,
+  };
+  // The last recorded of the smallest elements, usually the innermost one.
+  let hovered = elements
+    .iter()
+    .filter(|(rect, _)| rect.contains(pointer))
+    .rev()
+    .min_by(|(a, _), (b, _)| a.area().total_cmp(&b.area()));
+  let (rect, celt) = match hovered {
+    Some(hovered) => hovered,
+    None => return,
+  };
+
+  let highlight = Color32::from_rgb(255, 0, 255);
+  ctx
+    .layer_painter(LayerId::new(Order::Tooltip, id()))
+    .rect_stroke(*rect, 0.0, Stroke { width: 1.0, color: highlight });
+
+  // Keep the panel away from the pointer.
+  let screen = ctx.input().screen_rect();
+  let align = if pointer.x < screen.center().x { Align2::RIGHT_TOP } else { Align2::LEFT_TOP };
+  let offset = if align == Align2::RIGHT_TOP { [-8.0, 8.0] } else { [8.0, 8.0] };
+  egui::Area::new(id().with("panel"))
+    .order(Order::Tooltip)
+    .interactable(false)
+    .anchor(align, offset)
+    .show(ctx, |ui| {
+      Frame::popup(ui.style()).show(ui, |ui| {
+        ui.set_max_width(screen.width() / 2.0 - 16.0);
+        let element = celt.element();
+        ui.label(RichText::new(element.to_string()).monospace().color(highlight));
+        ui.separator();
+
+        let matched = rules.explain(element);
+        if matched.is_empty() {
+          ui.label("No matching rule");
+        }
+        for rule in matched {
+          let important = rule.important_properties().into_iter().map(|name| format!("{} !important", name));
+          let properties: Vec<String> = rule.properties().into_iter().map(String::from).chain(important).collect();
+          ui.label(RichText::new(format!("{} {:?}", rule.selector(), rule.specificity())).monospace());
+          ui.label(RichText::new(format!("  {}:{}  {}", rule.origin(), rule.line(), properties.join(", "))).small());
+        }
+        ui.separator();
+
+        // Properties with their default value are left out.
+        let defaults = ComputedProperties::default().fields();
+        Grid::new(id().with("properties")).num_columns(2).show(ui, |ui| {
+          for ((name, value), (_, default)) in celt.props().fields().into_iter().zip(defaults) {
+            if value != default {
+              ui.label(RichText::new(name).monospace());
+              ui.label(RichText::new(value).monospace());
+              ui.end_row();
+            }
+          }
+        });
+      });
+    });
+}
@@ -1,24 +1,26 @@
+use std::sync::Arc;
+
+use css::Display;
+use egui::epaint::text::TextWrapping;
 use egui::text::{LayoutJob, TextFormat};
-use egui::{vec2, Color32, Response, Sense, TextStyle, Ui, Widget, WidgetInfo, WidgetText, WidgetType};
+use egui::{vec2, Galley, Rect, Response, Sense, Ui, Vec2, Widget, WidgetInfo, WidgetType};
 use styling::element::ComputedElement;
-use styling::BoxProperties;
+use styling::{BoxProperties, TextLayout};
+
+use crate::ui::inspector;
 
 #[must_use = "You should put this widget in an ui with `ui.add(widget);`"]
 pub struct Label {
-  text: WidgetText,
-  box_properties: BoxProperties,
+  text: String,
+  celt: ComputedElement,
   sense: Sense,
 }
 
 impl Label {
   pub fn new(celt: &ComputedElement, text: &str) -> Self {
-    let mut job = LayoutJob::default();
-    let text_format: TextFormat = celt.clone().into();
-    job.append(text, 0.0, text_format);
-    let box_properties: BoxProperties = celt.clone().into();
     Self {
-      text: job.into(),
-      box_properties,
+      text: text.to_owned(),
+      celt: celt.clone(),
       sense: Sense::hover(),
     }
   }
@@ -29,59 +31,134 @@ impl Label {
   }
 }
 
+fn layout(ui: &Ui, text: &str, format: &TextFormat, wrap: TextWrapping) -> Arc<Galley> {
+  let mut job = LayoutJob::single_section(text.to_owned(), format.clone());
+  job.wrap = wrap;
+  ui.fonts().layout_job(job)
+}
+
+// Spaces the rows evenly, centered in their line.
+fn with_line_height(galley: Arc<Galley>, line_height: f32) -> Arc<Galley> {
+  let mut galley = (*galley).clone();
+  let mut top = galley.rect.min.y;
+  let mut mesh_bounds = Rect::NOTHING;
+  for row in &mut galley.rows {
+    let delta = vec2(0.0, top + (line_height - row.rect.height()) / 2.0 - row.rect.min.y);
+    row.rect = row.rect.translate(delta);
+    row.visuals.mesh.translate(delta);
+    row.visuals.mesh_bounds = row.visuals.mesh_bounds.translate(delta);
+    for glyph in &mut row.glyphs {
+      glyph.pos += delta;
+    }
+    mesh_bounds = mesh_bounds.union(row.visuals.mesh_bounds);
+    top += line_height;
+  }
+  galley.rect.max.y = top;
+  galley.mesh_bounds = mesh_bounds;
+  Arc::new(galley)
+}
+
 impl Widget for Label {
   fn ui(self, ui: &mut Ui) -> Response {
-    let wrap = Some(false);
-    let wrap_width = f32::INFINITY; // Never wrap
-    let style_fallback = TextStyle::Body;
-    let text = self.text.into_galley(ui, wrap, wrap_width, style_fallback);
+    let celt = self.celt.available_size(ui.available_size());
+    if celt.props().display == Display::None {
+      return ui.allocate_response(Vec2::ZERO, Sense::hover());
+    }
+    let box_properties: BoxProperties = celt.clone().into();
+    let text_layout: TextLayout = celt.clone().into();
+    let text_format: TextFormat = celt.clone().into();
+
+    // Room for the text when the label can't grow.
+    let max_width = if box_properties.width != 0.0 {
+      box_properties.width
+    } else {
+      box_properties.max_width.min(ui.available_width())
+    };
+    let max_text_width = (max_width - box_properties.padding.sum().x).max(0.0);
+
+    let mut text = if text_layout.wrap {
+      layout(
+        ui,
+        &self.text,
+        &text_format,
+        TextWrapping {
+          max_width: max_text_width,
+          ..Default::default()
+        },
+      )
+    } else {
+      layout(ui, &self.text, &text_format, TextWrapping::default())
+    };
+    let truncated = !text_layout.wrap && text_layout.ellipsis && text.size().x > max_text_width;
+    if truncated {
+      text = layout(
+        ui,
+        &self.text,
+        &text_format,
+        TextWrapping {
+          max_width: max_text_width,
+          max_rows: 1,
+          break_anywhere: true,
+          overflow_character: Some('…'),
+        },
+      );
+    }
+    if text_layout.line_height > 0.0 {
+      text = with_line_height(text, text_layout.line_height);
+    }
 
     let text_size = text.size();
 
-    let mut height = if self.box_properties.height != 0.0 {
-      self.box_properties.height
+    let mut height = if box_properties.height != 0.0 {
+      box_properties.height
     } else {
-      text_size.y + self.box_properties.padding.sum().y
+      text_size.y + box_properties.padding.sum().y
     };
-    height = height.clamp(self.box_properties.min_height, self.box_properties.max_height);
+    height = height.clamp(box_properties.min_height, box_properties.max_height);
 
-    let mut width = if self.box_properties.width != 0.0 {
-      self.box_properties.width
+    let mut width = if box_properties.width != 0.0 {
+      box_properties.width
     } else {
-      text_size.x + self.box_properties.padding.sum().x
+      text_size.x + box_properties.padding.sum().x
     };
-    width = width.clamp(self.box_properties.min_width, self.box_properties.max_width);
+    width = width.clamp(box_properties.min_width, box_properties.max_width);
 
     let size = vec2(width, height);
 
-    let (rect, response) = ui.allocate_exact_size(size, self.sense);
+    let (rect, mut response) = ui.allocate_exact_size(size, self.sense);
 
     // FIXME: I'm sure there's a method to do that better
     let mut padding_less = rect;
-    padding_less.min.x += self.box_properties.padding.left;
-    padding_less.min.y += self.box_properties.padding.top;
-    padding_less.max.x -= self.box_properties.padding.right;
-    padding_less.max.y -= self.box_properties.padding.bottom;
+    padding_less.min.x += box_properties.padding.left;
+    padding_less.min.y += box_properties.padding.top;
+    padding_less.max.x -= box_properties.padding.right;
+    padding_less.max.y -= box_properties.padding.bottom;
 
-    let text_pos = self.box_properties.align.align_size_within_rect(text.size(), padding_less);
+    let text_pos = box_properties.align.align_size_within_rect(text.size(), padding_less);
 
-    response.widget_info(|| WidgetInfo::labeled(WidgetType::Label, text.text()));
+    response.widget_info(|| WidgetInfo::labeled(WidgetType::Label, &self.text));
 
     if ui.is_rect_visible(rect) {
       ui.scope(|ui| {
         ui.painter().rect(
-          rect.expand(self.box_properties.border.width),
-          self.box_properties.rounding,
-          self.box_properties.background,
-          self.box_properties.border,
+          rect.expand(box_properties.border.width),
+          box_properties.rounding,
+          box_properties.background,
+          box_properties.border,
         );
         let clip = ui.clip_rect();
         ui.set_clip_rect(padding_less.intersect(clip));
-        text.paint_with_fallback_color(ui.painter(), text_pos.min, Color32::RED);
+        ui.painter().galley(text_pos.min, text);
         ui.set_clip_rect(clip);
       });
     }
 
+    inspector::record(ui.ctx(), rect, &celt);
+
+    if truncated {
+      response = response.on_hover_text(self.text);
+    }
+
     response
   }
 }
@@ -1,50 +1,38 @@
 use css::Rules;
 use egui::{Frame, ScrollArea, Ui};
-use styling::element as elt;
+use styling::element::{self as elt, Element};
 
 use crate::server::Envelope;
+use crate::ui::container::Container;
 use crate::ui::label::Label;
 
+fn header(ui: &mut Ui, rules: &Rules, hbox: &Element, name: &str, value: &str) {
+  let label_elt = elt::label().classes("label").parent(hbox.clone());
+  let value_elt = elt::label().classes("value").parent(hbox.clone());
+  Container::new(rules, hbox)
+    .child(&label_elt, |ui| {
+      ui.add(Label::new(&label_elt.compute(rules), name));
+    })
+    .child(&value_elt, |ui| {
+      ui.add(Label::new(&value_elt.compute(rules), value));
+    })
+    .show(ui);
+}
+
 pub fn update(ui: &mut Ui, rules: &Rules, envelope: &Envelope, body: Option<&String>) {
   ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
-    // FIXME NOW: implement a FullWidthFrame ui widget.
-    // Nope. Main frames show justify content.
-    // FIXME: this should go beyond styling. And include vertical/horizontal layout
     let headers_elt = elt::vbox().id("body-headers");
-    let frame: Frame = headers_elt.compute(rules).into();
-    frame.show(ui, |ui| {
-      ui.horizontal(|ui| {
-        ui.vertical(|ui| {
-          let hbox = elt::hbox().id("body-headers-from").parent(headers_elt.clone());
-          ui.horizontal(|ui| {
-            let label_elt = elt::label().classes("label").parent(hbox.clone());
-            let value_elt = elt::label().classes("value").parent(hbox.clone());
-            ui.add(Label::new(&label_elt.compute(rules), "From: "));
-            ui.add(Label::new(&value_elt.compute(rules), &envelope.sender));
-          });
-
-          let hbox = elt::hbox().id("body-headers-subject").parent(headers_elt.clone());
-          ui.horizontal(|ui| {
-            let label_elt = elt::label().classes("label").parent(hbox.clone());
-            let value_elt = elt::label().classes("value").parent(hbox.clone());
-            ui.add(Label::new(&label_elt.compute(rules), "Subject: "));
-            ui.add(Label::new(&value_elt.compute(rules), &envelope.subject));
-          });
-
-          let hbox = elt::hbox().id("body-headers-date").parent(headers_elt.clone());
-          ui.horizontal(|ui| {
-            let label_elt = elt::label().classes("label").parent(hbox.clone());
-            let value_elt = elt::label().classes("value").parent(hbox.clone());
-            ui.add(Label::new(&label_elt.compute(rules), "Date: "));
-            let date = envelope.date.as_deref().unwrap_or("n/a");
-            ui.add(Label::new(&value_elt.compute(rules), date));
-          });
-        });
-        ui.add_space(ui.available_width());
-      });
-    });
+    let from_elt = elt::hbox().id("body-headers-from").parent(headers_elt.clone());
+    let subject_elt = elt::hbox().id("body-headers-subject").parent(headers_elt.clone());
+    let date_elt = elt::hbox().id("body-headers-date").parent(headers_elt.clone());
+    let date = envelope.date.as_deref().unwrap_or("n/a");
+    Container::new(rules, &headers_elt)
+      .child(&from_elt, |ui| header(ui, rules, &from_elt, "From: ", &envelope.sender))
+      .child(&subject_elt, |ui| header(ui, rules, &subject_elt, "Subject: ", &envelope.subject))
+      .child(&date_elt, |ui| header(ui, rules, &date_elt, "Date: ", date))
+      .show(ui);
 
-    let frame: Frame = elt::hbox().id("body-content").compute(rules).into();
+    let frame: Frame = elt::hbox().id("body-content").compute(rules).available_size(ui.available_size()).into();
     frame.show(ui, |ui| {
       if let Some(body) = body {
         ui.label(body);
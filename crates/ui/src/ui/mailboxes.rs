@@ -1,5 +1,5 @@
 use css::Rules;
-use egui::{CollapsingHeader, Sense, Ui};
+use egui::{CollapsingHeader, Id, Sense, Ui};
 use styling::element as elt;
 
 use crate::server::{MboxId, Mboxes};
@@ -28,15 +28,24 @@ pub fn update(ui: &mut Ui, rules: &Rules, mboxes: &Mboxes, selected_mbox: &Optio
   let mut ret = None;
   for (account, mboxes) in mboxes {
     CollapsingHeader::new(account.trim()).default_open(true).show(ui, |ui| {
-      for mbox in mboxes {
+      for (index, mbox) in mboxes.iter().enumerate() {
         let selected = selected_mbox
           .as_ref()
           .map_or(false, |selected_mbox| &selected_mbox.account == account && &selected_mbox.name == mbox);
 
-        let mut hbox = elt::hbox().classes("folder-listitem");
+        let mut hbox = elt::hbox().classes("folder-listitem").position(index, mboxes.len());
         hbox.toggle_class("selected", selected);
-        let icon_elt = elt::label().classes("icon").parent(hbox.clone()).compute(rules);
-        let name_elt = elt::label().classes("name").parent(hbox.clone()).compute(rules);
+        let id = Id::new("folder-listitem").with(account).with(mbox);
+        let icon_elt = elt::label()
+          .classes("icon")
+          .parent(hbox.clone())
+          .compute(rules)
+          .animate(ui.ctx(), id.with("icon"));
+        let name_elt = elt::label()
+          .classes("name")
+          .parent(hbox.clone())
+          .compute(rules)
+          .animate(ui.ctx(), id.with("name"));
 
         ui.horizontal(|ui| {
           let display_name = if mbox.starts_with("[Gmail]/") { mbox.get(8..).unwrap() } else { mbox };
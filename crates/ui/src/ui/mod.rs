@@ -1,5 +1,8 @@
+pub mod container;
 pub mod envelopes;
+pub mod inspector;
 pub mod label;
 pub mod mailbody;
 pub mod mailboxes;
+pub mod theme_errors;
 pub mod toolbar;
@@ -0,0 +1,33 @@
+//! Banner listing why the last theme reload failed. The last good theme is
+//! used meanwhile.
+
+use css::Rules;
+use egui::Ui;
+use styling::element::{self as elt, Element};
+
+use crate::theme::ThemeError;
+use crate::ui::container::Container;
+use crate::ui::label::Label;
+
+/// Returns true if the banner was dismissed.
+pub fn update(ui: &mut Ui, rules: &Rules, parent: &Element, errors: &[ThemeError]) -> bool {
+  let hbox = elt::hbox().parent(parent.clone());
+  let messages_elt = elt::vbox().classes("messages").parent(hbox.clone());
+  let dismiss_elt = elt::native().classes("dismiss").parent(hbox.clone());
+  let mut dismissed = false;
+  Container::new(rules, &hbox)
+    .child(&messages_elt, |ui| {
+      let mut messages = Container::new(rules, &messages_elt);
+      for error in errors {
+        let label_elt = elt::label().classes("error").parent(messages_elt.clone());
+        let label = Label::new(&label_elt.compute(rules), &error.to_string());
+        messages = messages.child(&label_elt, |ui| {
+          ui.add(label);
+        });
+      }
+      messages.show(ui);
+    })
+    .child(&dismiss_elt, |ui| dismissed = ui.button("Dismiss").clicked())
+    .show(ui);
+  dismissed
+}
@@ -1,17 +1,35 @@
 use css::Rules;
-use egui::{Frame, Ui};
+use egui::{ComboBox, Ui};
 use styling::element as elt;
 
-pub fn update(ui: &mut Ui, rules: &Rules, parent: &elt::Element) -> bool {
-  let frame: Frame = elt::hbox().id("toolbar").parent(parent.clone()).compute(rules).into();
-  let response = frame.show(ui, |ui| {
-    ui.horizontal(|ui| {
-      let clicked = ui.button("Sidebar").clicked();
-      // FIXME: I wish we didn't have to do that. Content should be justified.
-      ui.add_space(ui.available_width());
-      clicked
-    })
-  });
+use crate::theme::Appearance;
+use crate::ui::container::Container;
+
+fn appearance_label(appearance: Appearance) -> &'static str {
+  match appearance {
+    Appearance::Auto => "Auto",
+    Appearance::Light => "Light",
+    Appearance::Dark => "Dark",
+  }
+}
 
-  response.inner.inner
+/// Returns true if the sidebar button was clicked.
+pub fn update(ui: &mut Ui, rules: &Rules, parent: &elt::Element, appearance: &mut Appearance) -> bool {
+  let toolbar_elt = elt::hbox().id("toolbar").parent(parent.clone());
+  let button_elt = elt::native().classes("sidebar-button").parent(toolbar_elt.clone());
+  let appearance_elt = elt::native().classes("appearance-selector").parent(toolbar_elt.clone());
+  let mut clicked = false;
+  Container::new(rules, &toolbar_elt)
+    .child(&button_elt, |ui| clicked = ui.button("Sidebar").clicked())
+    .child(&appearance_elt, |ui| {
+      ComboBox::from_id_source("appearance-selector")
+        .selected_text(appearance_label(*appearance))
+        .show_ui(ui, |ui| {
+          for choice in [Appearance::Auto, Appearance::Light, Appearance::Dark] {
+            ui.selectable_value(appearance, choice, appearance_label(choice));
+          }
+        });
+    })
+    .show(ui);
+  clicked
 }